@@ -1,23 +1,136 @@
-use clap::{arg, crate_authors, crate_version, Command};
+use clap::{arg, crate_authors, crate_version, Arg, Command};
 
 const APP_NAME: &str = "tc";
 
+fn show_arg() -> Arg {
+    arg!(show: --show "Print the list after the change")
+}
+
 pub fn cli() -> Command {
     Command::new(APP_NAME)
         .about("(T)ime (C)onverter. For those who have to constantly deal with timezones.")
         .version(crate_version!())
         .author(crate_authors!("\n"))
+        .arg(
+            arg!(no_migrate: --"no-migrate" "Fail instead of rewriting an older config")
+                .global(true),
+        )
+        .arg(
+            arg!(no_write: --"no-write" "Never write the config, using an empty one if there isn't one")
+                .global(true),
+        )
+        .arg(
+            arg!(strict: --strict "Only accept exact timezone names or nicknames").global(true),
+        )
+        .arg(arg!(config: --config <PATH> "Use this config file instead of the usual one").global(true))
+        .arg(
+            arg!(verbose: -v --verbose "Explain config loading and timezone matching on stderr")
+                .global(true),
+        )
         .subcommand(
             Command::new("t")
                 .about("Default - Get time based on defined timezones")
-                .arg(arg!(time: [TIME]))
-                .arg(arg!(timezone: -t --timezone [TIMEZONE] "Offset by timezone"))
+                .arg(arg!(time: [TIME] ... "One or more times to convert"))
+                .arg(
+                    arg!(timezone: -t --timezone [TIMEZONE] "Read the time in this timezone or fixed offset like +5:45, or several separated by commas")
+                        .visible_alias("input-timezone")
+                        .allow_hyphen_values(true),
+                )
                 .arg(
                     arg!(output: -o --output [OUTPUT] "Set output format")
-                        .value_parser(["pretty", "json", "json_pretty", "csv"])
+                        .value_parser([
+                            "pretty",
+                            "json",
+                            "json_pretty",
+                            "csv",
+                            "tsv",
+                            "plain",
+                            "html",
+                            "oneline",
+                            "svg",
+                            "count",
+                            "template",
+                            "url",
+                        ])
                         .default_value("pretty")
                         .default_missing_value("pretty"),
                 )
+                .arg(
+                    arg!(pretty_width: --"pretty-width" <WIDTH> "Width of the name column in pretty output")
+                        .value_parser(clap::value_parser!(usize))
+                        .default_value("25"),
+                )
+                .arg(
+                    arg!(min_width: --"min-width" <WIDTH> "Fit the name column to the longest name, but at least this wide")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("pretty_width"),
+                )
+                .arg(
+                    arg!(max_width: --"max-width" <WIDTH> "Fit the name column to the longest name, but at most this wide")
+                        .value_parser(clap::value_parser!(usize))
+                        .conflicts_with("pretty_width"),
+                )
+                .arg(
+                    arg!(oneline_separator: --"oneline-separator" <SEPARATOR> "Text between zones in oneline output")
+                        .default_value(" | "),
+                )
+                .arg(arg!(header_format: --"header-format" <FORMAT> "Template for the pretty header, using {zone}, {time} and {date}"))
+                .arg(
+                    arg!(header_zone_name: --"header-zone-name" <STYLE> "Name the reference in the header by its timezone, nickname and timezone, or nickname")
+                        .value_parser(["name", "full", "nick"])
+                        .default_value("name"),
+                )
+                .arg(arg!(template: --template <TEMPLATE> "Row for template output, using {field} for any field --columns accepts"))
+                .arg(
+                    arg!(format_file: --"format-file" <PATH> "Render through a template file with [header], [row] and [footer] sections")
+                        .conflicts_with_all(["template", "output"]),
+                )
+                .arg(arg!(columns: --columns <COLUMNS> "Comma-separated fields to include in csv, tsv and json output"))
+                .arg(
+                    arg!(json_min: --"json-min" "Json output with only timezone_name, timestamp, utc_offset_seconds and day_offset")
+                        .conflicts_with_all(["columns", "format_file", "template"]),
+                )
+                .arg(
+                    arg!(group_by: --"group-by" <GROUP> "Split pretty output into sections")
+                        .value_parser(["day"]),
+                )
+                .arg(
+                    arg!(anchor: --anchor <TIMEZONE> "Measure day offsets and deltas from this timezone")
+                        .allow_hyphen_values(true),
+                )
+                .arg(arg!(delta: --delta "Show each timezone's offset from the reference in pretty output"))
+                .arg(arg!(show_date: --"show-date" "Show the date for each timezone in pretty output"))
+                .arg(arg!(subsec: --subsec "Show times to the millisecond"))
+                .arg(
+                    arg!(sort: --sort <ORDER> "Sort by time, or by offset ahead of the reference")
+                        .value_parser(["time", "from-pivot"])
+                        .default_value("time"),
+                )
+                .arg(arg!(copy: --copy "Also copy the output to the clipboard, except with --curses"))
+                .arg(
+                    arg!(highlight: --highlight <TIMEZONE> "Mark this saved timezone's row in pretty output")
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    arg!(zones: --zones <TIMEZONES> "Show these comma-separated timezones instead of the saved list")
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    arg!(wrap: --wrap <COLUMNS> "Wrap pretty rows to this many columns")
+                        .value_parser(clap::value_parser!(usize)),
+                )
+                .arg(
+                    arg!(bare_number: --"bare-number" <MODE> "Read a lone number like 15 as an hour, or like 1700000000 as a timestamp")
+                        .value_parser(["hour", "epoch"])
+                        .default_value("hour"),
+                )
+                .arg(arg!(cross_day_only: --"cross-day-only" "Only show timezones on a different day to the reference"))
+                .arg(
+                    arg!(same_day_only: --"same-day-only" "Only show timezones on the same day as the reference")
+                        .conflicts_with("cross_day_only"),
+                )
+                .arg(arg!(truncate_names: --"truncate-names" "Show only the city of each timezone in pretty output"))
+                .arg(arg!(reverse: -r --reverse "Reverse the order of the list"))
                 .arg(arg!(curses: -c --curses "Keep active and looping with curses")),
         )
         .subcommand(
@@ -26,33 +139,186 @@ pub fn cli() -> Command {
                 .subcommand(
                     Command::new("add")
                         .about("Add a new timezone to the list")
-                        .arg(arg!(timezone: [TIMEZONE])),
+                        .arg(arg!(timezone: [TIMEZONE]))
+                        .arg(arg!(nick: -n --nick <NICKNAME> "Nickname to give the timezone"))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("add-local")
+                        .about("Add the system's own timezone to the list")
+                        .arg(show_arg()),
                 )
                 .subcommand(
                     Command::new("nick")
                         .about("Add a nickname to a timezone")
                         .arg(arg!(timezone: [TIMEZONE]))
-                        .arg(arg!(nickname: [NICKNAME] "Leave blank to clear nickname")),
+                        .arg(arg!(nickname: [NICKNAME] "Leave blank to clear nickname"))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("note")
+                        .about("Add a note to a timezone, shown alongside it")
+                        .arg(arg!(timezone: [TIMEZONE]))
+                        .arg(arg!(note: [NOTE] "Leave blank to clear note"))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("pin")
+                        .about("Toggle keeping the provided timezone at the top of the list")
+                        .arg(arg!(timezone: [TIMEZONE]))
+                        .arg(show_arg()),
                 )
                 .subcommand(
                     Command::new("sep")
                         .about(
                             "Add a separator after the provided timezone when using pretty output",
                         )
+                        .arg(arg!(timezone: [TIMEZONE]))
+                        .arg(arg!(style: --style <STYLE> "dash, double, blank, or a label to show in the line"))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("import")
+                        .about("Replace the list with timezones from a config file")
+                        .arg(arg!(path: [PATH]))
+                        .arg(arg!(merge: -m --merge "Add to the list instead of replacing it"))
+                        .arg(
+                            arg!(update: -u --update "Also update nicknames and separators of timezones already added")
+                                .requires("merge"),
+                        )
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("info")
+                        .about("Show details about an added timezone")
                         .arg(arg!(timezone: [TIMEZONE])),
                 )
-                .subcommand(Command::new("list").about("List added timezones"))
+                .subcommand(
+                    Command::new("list")
+                        .about("List added timezones")
+                        .arg(arg!(json: --json "Print the saved config as json"))
+                        .arg(
+                            arg!(sort: --sort <ORDER> "Order to list timezones in")
+                                .value_parser(["name", "offset", "added"])
+                                .default_value("added"),
+                        ),
+                )
+                .subcommand(Command::new("migrate").about("Update an older config to the current version"))
+                .subcommand(
+                    Command::new("prune")
+                        .about("Remove timezones that haven't been used with -t in a while")
+                        .arg(
+                            arg!(older_than: --"older-than" <DAYS> "Remove timezones unused for at least this many days")
+                                .value_parser(clap::value_parser!(u64))
+                                .required(true),
+                        )
+                        .arg(arg!(yes: -y --yes "Don't ask before removing"))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("reorder")
+                        .about("Reorder saved timezones interactively, with the arrow keys moving the highlighted one")
+                        .arg(show_arg()),
+                )
+                .subcommand(Command::new("nicks").about("List nicknames and their timezones"))
                 .subcommand(
                     Command::new("remove")
                         .about("Remove added timezone")
-                        .arg(arg!(timezone: [TIMEZONE])),
+                        .arg(arg!(timezone: [TIMEZONE]))
+                        .arg(show_arg()),
+                )
+                .subcommand(
+                    Command::new("suggest")
+                        .about("Find timezones for a city, with their current offsets")
+                        .arg(arg!(city: [CITY])),
                 )
-                .subcommand(Command::new("list-available").about("List possible timezones to add")),
+                .subcommand(
+                    Command::new("list-available")
+                        .about("List possible timezones to add")
+                        .arg(
+                            arg!(offset: --offset <OFFSET> "Only timezones currently at this UTC offset")
+                                .allow_hyphen_values(true),
+                        )
+                        .arg(
+                            arg!(country: --country <CODE> "Only timezones in this country, by ISO 3166 code like BR")
+                                .conflicts_with("offset"),
+                        ),
+                ),
+        )
+        .subcommand(
+            Command::new("dst")
+                .about("List this year's DST transitions for a timezone")
+                .arg(arg!(timezone: [TIMEZONE])),
+        )
+        .subcommand(
+            Command::new("gap")
+                .about("Show how far one timezone is ahead of another right now")
+                .arg(arg!(from: [FROM]).allow_hyphen_values(true))
+                .arg(arg!(to: [TO]).allow_hyphen_values(true)),
         )
         .subcommand(
             Command::new("u")
                 .about("Turn provided time into UNIX timestamp")
                 .arg(arg!(discord: -d --discord "Format for Discord timestamp"))
+                .arg(
+                    arg!(style: -s --style <STYLE> "Discord timestamp style, implies --discord")
+                        .value_parser(["t", "T", "d", "D", "f", "F", "R"]),
+                )
+                .arg(
+                    arg!(bare_number: --"bare-number" <MODE> "Read a lone number like 15 as an hour, or like 1700000000 as a timestamp")
+                        .value_parser(["hour", "epoch"])
+                        .default_value("hour"),
+                )
+                .arg(arg!(copy: --copy "Also copy the output to the clipboard"))
+                .arg(arg!(preview: --preview "Also print roughly how Discord will show the timestamp"))
+                .arg(
+                    arg!(from_zone: -f --"from-zone" <TIMEZONE> "Read the time in this timezone instead of local")
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    arg!(prefer: --prefer <WHICH> "Instant to use when the time is ambiguous or skipped by DST")
+                        .value_parser(["earlier", "later"]),
+                )
+                .arg(
+                    arg!(file: --file <PATH> "Convert each line of a file instead")
+                        .conflicts_with("time"),
+                )
+                .arg(arg!(csv: --csv "Print each result as csv alongside its input"))
                 .arg(arg!(time: [TIME])),
         )
+        .subcommand(
+            Command::new("schedule")
+                .about("Describe a time in each saved timezone, for announcements")
+                .arg(arg!(time: [TIME]))
+                .arg(
+                    arg!(timezone: -t --timezone <TIMEZONE> "Timezone the time is in")
+                        .allow_hyphen_values(true),
+                )
+                .arg(
+                    arg!(output: -o --output <OUTPUT> "Set output format")
+                        .value_parser(["text", "json"])
+                        .default_value("text"),
+                ),
+        )
+        .subcommand(
+            Command::new("cron")
+                .about("Print a daily cron and systemd OnCalendar line for a time, in UTC")
+                .arg(arg!(time: <TIME>))
+                .arg(
+                    arg!(timezone: -t --timezone <TIMEZONE> "Timezone the time is in")
+                        .allow_hyphen_values(true),
+                ),
+        )
+        .subcommand(
+            Command::new("doctor").about("Print config and environment details for bug reports"),
+        )
+        .subcommand(
+            Command::new("version")
+                .about("Print version information")
+                .arg(arg!(json: --json "Print as json, including the tzdata version"))
+                .arg(
+                    arg!(short: --short "Print only the version number, for scripts")
+                        .conflicts_with("json"),
+                ),
+        )
 }
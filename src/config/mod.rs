@@ -1,62 +1,318 @@
-use chrono_tz::TZ_VARIANTS;
 use confy::ConfyError;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 use serde_derive::{Deserialize, Serialize};
-use std::str::FromStr;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use crate::time_helpers;
 
 const APP_NAME: &str = "tc";
+const CONFIG_VERSION: u8 = 7;
+
+static NO_MIGRATE: AtomicBool = AtomicBool::new(false);
+static STRICT: AtomicBool = AtomicBool::new(false);
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+static NO_WRITE: AtomicBool = AtomicBool::new(false);
+static CONFIG_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+pub fn set_no_migrate(value: bool) {
+    NO_MIGRATE.store(value, Ordering::Relaxed);
+}
+
+pub fn set_no_write(value: bool) {
+    NO_WRITE.store(value, Ordering::Relaxed);
+}
+
+pub fn set_strict(value: bool) {
+    STRICT.store(value, Ordering::Relaxed);
+}
+
+pub fn is_strict() -> bool {
+    STRICT.load(Ordering::Relaxed)
+}
+
+pub fn set_config_path(path: Option<String>) {
+    *CONFIG_PATH.lock().unwrap() = path.map(PathBuf::from);
+}
+
+// `--config`, then a `.tc.toml` in the working directory or any parent, then `TC_CONFIG`. `None`
+// leaves it to confy's usual location.
+fn config_path() -> Option<PathBuf> {
+    if let Some(t) = CONFIG_PATH.lock().unwrap().clone() {
+        return Some(t);
+    }
+    if let Some(t) = project_config() {
+        return Some(t);
+    }
+    env::var("TC_CONFIG")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .map(PathBuf::from)
+}
+
+// A `.tc.toml` in the working directory or any parent.
+fn project_config() -> Option<PathBuf> {
+    let dir = env::current_dir().ok()?;
+    dir.ancestors()
+        .map(|parent| parent.join(".tc.toml"))
+        .find(|candidate| candidate.is_file())
+}
+
+pub fn set_verbose(value: bool) {
+    VERBOSE.store(value, Ordering::Relaxed);
+}
+
+pub fn is_verbose() -> bool {
+    VERBOSE.load(Ordering::Relaxed)
+}
+
+// `eprintln!` that only prints with `--verbose`.
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::config::is_verbose() {
+            eprintln!("[verbose] {}", format_args!($($arg)*));
+        }
+    };
+}
+pub(crate) use verbose;
+
+// Nicknames can be several words, so stray and doubled spaces are tidied away before saving
+// and matching.
+pub fn normalize_nickname(nickname: &str) -> String {
+    nickname.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SavedTimezones {
     pub timezone_name: String,
     pub nickname: Option<String>,
     pub separator: bool,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    // `dash` when unset, otherwise `double`, `blank` or a label to put in the line.
+    #[serde(default)]
+    pub separator_style: Option<String>,
+    // Unix time this zone was last read from with `tc t -t`, for `tc d prune`.
+    #[serde(default)]
+    pub last_used: Option<i64>,
+    // Fields written by a newer tc are kept as-is so saving from this one doesn't drop them.
+    #[serde(flatten)]
+    pub extra: HashMap<String, toml::Value>,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
 struct SavedTimezonesV1 {
     timezone_name: String,
     nickname: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, toml::Value>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SavedDefines {
     pub version: u8,
+    // Where `tc t -o url` links to, in place of the default.
+    #[serde(default)]
+    pub url_base: Option<String>,
     pub timezones: Vec<SavedTimezones>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, toml::Value>,
+}
+
+// Just enough of any config version to read which version it is.
+#[derive(Deserialize)]
+struct ConfigVersion {
+    #[serde(default)]
+    version: i64,
+    #[serde(default)]
+    timezones: Vec<toml::Value>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
 struct SavedDefinesV1 {
     version: u8,
     timezones: Vec<SavedTimezonesV1>,
+    #[serde(flatten)]
+    extra: HashMap<String, toml::Value>,
 }
 
 #[derive(Serialize, Deserialize, Default)]
 struct SavedDefinesV0 {
     version: u8,
     timezones: Vec<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, toml::Value>,
+}
+
+// What `tc d import` reads. Everything but the name is optional so a hand-written partial list
+// works as well as a copy of another machine's config.
+#[derive(Deserialize)]
+struct ImportedDefines {
+    timezones: Vec<ImportedTimezone>,
+}
+
+#[derive(Deserialize)]
+struct ImportedTimezone {
+    timezone_name: String,
+    nickname: Option<String>,
+    #[serde(default)]
+    separator: bool,
+    #[serde(default)]
+    pinned: bool,
+    note: Option<String>,
+    separator_style: Option<String>,
+    #[serde(flatten)]
+    extra: HashMap<String, toml::Value>,
 }
 
 impl ::std::default::Default for SavedDefines {
     fn default() -> Self {
         Self {
-            version: 2,
+            version: CONFIG_VERSION,
+            url_base: None,
             timezones: [].to_vec(),
+            extra: HashMap::new(),
         }
     }
 }
 
+// Going through `toml::Value` puts tables after plain values, which the toml serializer requires
+// once unknown fields are flattened back in after `timezones`.
+pub fn store_config(config: &SavedDefines) -> Result<(), ConfyError> {
+    if NO_WRITE.load(Ordering::Relaxed) {
+        eprintln!("Not saving config because of --no-write!");
+        return Err(ConfyError::WriteConfigurationFileError(io::Error::other(
+            "--no-write was given",
+        )));
+    }
+    let value = toml::Value::try_from(config).map_err(ConfyError::SerializeTomlError)?;
+    match config_path() {
+        Some(path) => confy::store_path(path, value),
+        None => confy::store(APP_NAME, None, value),
+    }
+}
+
+// A config migrated on the way to running another command is still usable with `--no-write`, it
+// just isn't saved. `tc d migrate` refuses instead, see `migrate_config`.
+fn store_migrated(config: &SavedDefines) -> Result<(), ConfyError> {
+    if NO_WRITE.load(Ordering::Relaxed) {
+        eprintln!("Updated in memory only because of --no-write, continuing.");
+        return Ok(());
+    }
+    store_config(config)?;
+    eprintln!("Update successful, continuing.");
+    Ok(())
+}
+
+// The file the config is read from, whether or not it exists yet.
+pub fn config_file() -> Option<PathBuf> {
+    config_path().or_else(|| confy::get_configuration_file_path(APP_NAME, None).ok())
+}
+
+// The version and number of timezones in the config file, read without loading it so nothing is
+// migrated or created. Also gives the newest version this tc supports.
+pub fn config_summary() -> (Option<(i64, usize)>, u8) {
+    let summary = config_file()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|t| toml::from_str::<ConfigVersion>(&t).ok())
+        .map(|t| (t.version, t.timezones.len()));
+    (summary, CONFIG_VERSION)
+}
+
+fn load_file<T: Serialize + DeserializeOwned + Default>() -> Result<T, ConfyError> {
+    // confy writes out a default config when there isn't one, so `--no-write` skips it.
+    if NO_WRITE.load(Ordering::Relaxed) && config_file().is_some_and(|t| !t.exists()) {
+        verbose!("No config file, using an empty one in memory");
+        return Ok(T::default());
+    }
+    match config_path() {
+        Some(path) => confy::load_path(path),
+        None => confy::load(APP_NAME, None),
+    }
+}
+
 pub fn load_config() -> Result<SavedDefines, ConfyError> {
-    let config: SavedDefines = match confy::load(APP_NAME, None) {
+    load_config_with(!NO_MIGRATE.load(Ordering::Relaxed))
+}
+
+// The version a config says it is, 0 when it doesn't say. Text that isn't toml is an error here
+// rather than something to migrate.
+fn file_version(text: &str) -> Result<i64, toml::de::Error> {
+    toml::from_str::<ConfigVersion>(text).map(|t| t.version)
+}
+
+fn load_config_with(allow_migrate: bool) -> Result<SavedDefines, ConfyError> {
+    let mut version: Option<i64> = None;
+    if let Some(path) = config_file() {
+        verbose!("Loading config from {}", path.display());
+        if let Ok(text) = fs::read_to_string(&path) {
+            version = match file_version(&text) {
+                Ok(t) => Some(t),
+                Err(e) => {
+                    eprintln!("Error loading config: {}", e);
+                    return Err(ConfyError::BadTomlData(e));
+                }
+            };
+        }
+        // Loading a newer config as this version would drop whatever it added when saved back.
+        if let Some(version) = version.filter(|t| *t > CONFIG_VERSION as i64) {
+            verbose!(
+                "Config is version {}, newest supported is {}",
+                version,
+                CONFIG_VERSION
+            );
+            eprintln!("Config written by a newer tc; upgrade tc or use --config.");
+            return Err(ConfyError::GeneralLoadError(io::Error::other(
+                "config is newer than supported",
+            )));
+        }
+    }
+    let mut config: SavedDefines = match load_file() {
         Ok(t) => t,
-        Err(_e) => {
+        Err(e) => {
+            verbose!("Loading as version {} failed: {}", CONFIG_VERSION, e);
+            // Only a config that doesn't deserialize can be an older version. Anything else,
+            // like a permissions problem, would fail the same way for every version.
+            if !matches!(e, ConfyError::BadTomlData(_)) {
+                eprintln!("Error loading config: {}", e);
+                return Err(e);
+            }
+            // Only versions 0 and 1 are laid out differently. A later config that doesn't load
+            // has a mistake in it, and migrating would throw away whatever didn't fit.
+            if version.is_some_and(|t| t >= 2) {
+                if let ConfyError::BadTomlData(inner) = &e {
+                    eprintln!("Error loading config: {}", inner);
+                }
+                return Err(e);
+            }
+            if !allow_migrate {
+                eprintln!("Older config found, run `tc d migrate` to update it.");
+                return Err(e);
+            }
             // ! Migrating configs is really annoying. There is surely a better way of doing it. For now... enjoy :D
             eprintln!("Older config found, updating config.");
-            let v1: SavedDefinesV1 = match confy::load(APP_NAME, None) {
-                Ok(t) => t,
-                Err(_e) => {
-                    let v0: SavedDefinesV0 = match confy::load(APP_NAME, None) {
-                        Ok(t) => t,
+            let v1: SavedDefinesV1 = match load_file() {
+                Ok(t) => {
+                    verbose!("Loaded as version 1");
+                    t
+                }
+                Err(e) => {
+                    verbose!("Loading as version 1 failed: {}", e);
+                    let v0: SavedDefinesV0 = match load_file() {
+                        Ok(t) => {
+                            verbose!("Loaded as version 0");
+                            t
+                        }
                         Err(e) => {
+                            verbose!("Loading as version 0 failed: {}", e);
                             eprintln!("Error loading config!");
                             return Err(e);
                         }
@@ -66,14 +322,15 @@ pub fn load_config() -> Result<SavedDefines, ConfyError> {
                         let new = SavedTimezonesV1 {
                             timezone_name: timezone,
                             nickname: None,
+                            extra: HashMap::new(),
                         };
                         new_tz_list.push(new);
                     }
-                    let new_config = SavedDefinesV1 {
+                    SavedDefinesV1 {
                         version: 1,
                         timezones: new_tz_list,
-                    };
-                    new_config
+                        extra: v0.extra,
+                    }
                 }
             };
             let mut new_tz_list: Vec<SavedTimezones> = [].to_vec();
@@ -82,15 +339,22 @@ pub fn load_config() -> Result<SavedDefines, ConfyError> {
                     timezone_name: timezone.timezone_name,
                     nickname: timezone.nickname,
                     separator: false,
+                    pinned: false,
+                    note: None,
+                    separator_style: None,
+                    last_used: Some(now()),
+                    extra: timezone.extra,
                 };
                 new_tz_list.push(new);
             }
             let new_config = SavedDefines {
-                version: 2,
+                version: CONFIG_VERSION,
+                url_base: None,
                 timezones: new_tz_list,
+                extra: v1.extra,
             };
-            match confy::store(APP_NAME, None, &new_config) {
-                Ok(_t) => eprintln!("Update successful, continuing."),
+            match store_migrated(&new_config) {
+                Ok(_t) => {}
                 Err(e) => {
                     eprintln!("Error saving config!");
                     return Err(e);
@@ -99,14 +363,118 @@ pub fn load_config() -> Result<SavedDefines, ConfyError> {
             new_config
         }
     };
+    // Everything added since version 2 has a default, so those configs only need the version
+    // bumped and writing back.
+    verbose!(
+        "Config is version {} with {} timezones",
+        config.version,
+        config.timezones.len()
+    );
+    if config.version < CONFIG_VERSION {
+        if !allow_migrate {
+            eprintln!("Older config found, run `tc d migrate` to update it.");
+            return Err(ConfyError::GeneralLoadError(io::Error::other(
+                "config needs migrating",
+            )));
+        }
+        eprintln!("Older config found, updating config.");
+        config.version = CONFIG_VERSION;
+        // Zones from before `last_used` count as used now, so pruning doesn't drop them all.
+        for timezone in config.timezones.iter_mut() {
+            timezone.last_used.get_or_insert(now());
+        }
+        match store_migrated(&config) {
+            Ok(_t) => {}
+            Err(e) => {
+                eprintln!("Error saving config!");
+                return Err(e);
+            }
+        };
+    }
     Ok(config)
 }
 
-pub fn saved_list_contains_timezone(defines: &SavedDefines, tz_name: &String) -> (i32, bool) {
+pub fn migrate_config() -> Option<String> {
+    if let Ok(t) = load_file::<SavedDefines>() {
+        if t.version == CONFIG_VERSION {
+            return Some("Config is already up to date.".to_string());
+        }
+    }
+    // Asking for a migration is asking for a write, unlike the one any other command does on the
+    // way, so it fails like any other save would.
+    if NO_WRITE.load(Ordering::Relaxed) {
+        eprintln!("Not migrating config because of --no-write!");
+        return None;
+    }
+    match load_config_with(true) {
+        Ok(t) => Some(format!("Config migrated to version {}.", t.version)),
+        Err(_e) => None,
+    }
+}
+
+fn now() -> i64 {
+    chrono::Utc::now().timestamp()
+}
+
+// Marks zones as just used. Nothing here is worth an error, so failures are only reported with
+// `--verbose`. Pruning counts in days, so a zone used within the last day is left as it is and
+// most runs don't write the config at all.
+pub fn touch_timezones(names: &[String]) {
+    if names.is_empty() || NO_WRITE.load(Ordering::Relaxed) {
+        return;
+    }
+    // A project's `.tc.toml` is usually committed, and reading from it shouldn't leave it dirty.
+    if CONFIG_PATH.lock().unwrap().is_none() && project_config().is_some() {
+        verbose!("Not marking timezones as used in a project config");
+        return;
+    }
+    let mut config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => return,
+    };
+    let day_ago = now() - 86400;
+    let mut changed = false;
+    for timezone in config.timezones.iter_mut() {
+        if names.contains(&timezone.timezone_name) && timezone.last_used.is_none_or(|t| t < day_ago)
+        {
+            timezone.last_used = Some(now());
+            changed = true;
+        }
+    }
+    if !changed {
+        return;
+    }
+    if let Err(e) = store_config(&config) {
+        verbose!("Couldn't save when timezones were last used: {}", e);
+    }
+}
+
+// Saved zones not used for at least `days` days, with how many days it has been.
+pub fn stale_timezones(days: u64) -> Option<Vec<(String, i64)>> {
+    let config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+    let now = now();
+    Some(
+        config
+            .timezones
+            .into_iter()
+            .filter_map(|t| {
+                let age = (now - t.last_used?) / 86400;
+                (age >= days as i64).then_some((t.timezone_name, age))
+            })
+            .collect(),
+    )
+}
+
+pub fn saved_list_contains_timezone(defines: &SavedDefines, tz_name: &str) -> (i32, bool) {
     let mut res = false;
     let mut index: i32 = -1;
     for (i, timezone) in defines.timezones.clone().iter().enumerate() {
-        if timezone.timezone_name == tz_name.clone() {
+        if timezone.timezone_name == tz_name {
             res = true;
             index = i as i32;
             break;
@@ -115,105 +483,271 @@ pub fn saved_list_contains_timezone(defines: &SavedDefines, tz_name: &String) ->
     (index, res)
 }
 
-pub fn add_timezone(tz_input: String) -> Option<String> {
+// Where the saved timezone `tz_input` resolves to sits in the list, or `None` after saying why
+// it isn't there. Matches looser than `max_rank` (see `match_rank`) are refused.
+pub fn saved_timezone_index(config: &SavedDefines, tz_input: &str, max_rank: u8) -> Option<usize> {
+    let not_found = "Timezone not found saved in config!";
+    let (res, rank) = time_helpers::resolve_zone_ranked(tz_input, config);
+    let tz = res.zone(tz_input, not_found)?;
+    if rank > max_rank {
+        eprintln!(
+            "{} only loosely matches {}! Use its name, city or nickname.",
+            tz_input,
+            tz.name()
+        );
+        return None;
+    }
+    match saved_list_contains_timezone(config, tz.name()) {
+        (i, true) => Some(i as usize),
+        _ => {
+            eprintln!("{}", not_found);
+            None
+        }
+    }
+}
+
+pub fn add_timezone(tz_input: String, nickname: Option<String>) -> Option<String> {
     let mut config = match load_config() {
         Ok(t) => t,
         Err(_e) => {
             return None;
         }
     };
-    for timezone in TZ_VARIANTS {
-        if tz_input
-            .to_lowercase()
-            .contains(&timezone.name().to_lowercase())
-        {
-            let tz_name = String::from_str(timezone.name()).unwrap();
-            if saved_list_contains_timezone(&config, &tz_name).1 {
-                eprintln!("Already exists in list!");
-                return None;
-            }
-            let new_timezone = SavedTimezones {
-                timezone_name: tz_name.clone(),
-                nickname: None,
-                separator: false,
-            };
-            config.timezones.push(new_timezone);
-            match confy::store(APP_NAME, None, &config) {
-                Ok(_t) => "",
-                Err(_e) => {
-                    eprintln!("Error saving config!");
-                    return None;
-                }
-            };
+    let timezone =
+        time_helpers::resolve_zone(&tz_input, &config).zone(&tz_input, "Timezone not found!")?;
+    let tz_name = timezone.name().to_string();
+    if saved_list_contains_timezone(&config, &tz_name).1 {
+        eprintln!("Already exists in list!");
+        return None;
+    }
+    let new_timezone = SavedTimezones {
+        timezone_name: tz_name.clone(),
+        nickname: nickname
+            .map(|t| normalize_nickname(&t))
+            .filter(|t| !t.is_empty()),
+        separator: false,
+        pinned: false,
+        note: None,
+        separator_style: None,
+        last_used: Some(now()),
+        extra: HashMap::new(),
+    };
+    config.timezones.push(new_timezone);
+    match store_config(&config) {
+        Ok(_t) => "",
+        Err(_e) => {
+            eprintln!("Error saving config!");
+            return None;
+        }
+    };
 
-            return Some(timezone.name().to_string());
+    Some(tz_name)
+}
+
+pub fn add_nick_to_timezone(tz_input: String, nickname: String) -> Option<String> {
+    let mut config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
         }
+    };
+    let i = saved_timezone_index(&config, &tz_input, u8::MAX)?;
+    let nickname = normalize_nickname(&nickname);
+    if nickname.is_empty() {
+        config.timezones[i].nickname = None;
+    } else {
+        config.timezones[i].nickname = Some(nickname);
     }
-    eprintln!("Timezone not found!");
-    return None;
+    match store_config(&config) {
+        Ok(_t) => "",
+        Err(_e) => {
+            eprintln!("Error saving config!");
+            return None;
+        }
+    };
+
+    Some(config.timezones[i].timezone_name.clone())
 }
 
-pub fn add_nick_to_timezone(tz_input: String, nickname: String) -> Option<String> {
+pub fn add_note_to_timezone(tz_input: String, note: String) -> Option<String> {
     let mut config = match load_config() {
         Ok(t) => t,
         Err(_e) => {
             return None;
         }
     };
-    let mut found = false;
-    for (i, timezone) in config.timezones.clone().into_iter().enumerate() {
-        if tz_input.contains(&timezone.timezone_name) {
-            if nickname.len() == 0 {
-                config.timezones[i].nickname = None;
-            } else {
-                config.timezones[i].nickname = Some(nickname);
+    let i = saved_timezone_index(&config, &tz_input, u8::MAX)?;
+    if note.is_empty() {
+        config.timezones[i].note = None;
+    } else {
+        config.timezones[i].note = Some(note);
+    }
+    match store_config(&config) {
+        Ok(_t) => "",
+        Err(_e) => {
+            eprintln!("Error saving config!");
+            return None;
+        }
+    };
+
+    Some(config.timezones[i].timezone_name.clone())
+}
+
+// Without a style this toggles the separator. With one it turns the separator on in that style.
+pub fn add_sep_to_timezone(tz_input: String, style: Option<String>) -> Option<String> {
+    let mut config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+    let i = saved_timezone_index(&config, &tz_input, u8::MAX)?;
+    let saved = &mut config.timezones[i];
+    match style {
+        Some(t) => {
+            saved.separator = true;
+            saved.separator_style = if t == "dash" { None } else { Some(t) };
+        }
+        None => {
+            saved.separator = !saved.separator;
+            if !saved.separator {
+                saved.separator_style = None;
             }
-            found = true;
-            break;
         }
     }
-    if !found {
-        eprintln!("Timezone not found saved in config!");
-        return None;
-    }
-    match confy::store(APP_NAME, None, &config) {
+    match store_config(&config) {
+        Ok(_t) => "",
+        Err(_e) => {
+            eprintln!("Error saving config!");
+            return None;
+        }
+    };
+    Some(config.timezones[i].timezone_name.clone())
+}
+
+pub fn toggle_pin_timezone(tz_input: String) -> Option<(String, bool)> {
+    let mut config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+    let i = saved_timezone_index(&config, &tz_input, u8::MAX)?;
+    config.timezones[i].pinned = !config.timezones[i].pinned;
+    let pinned = Some((
+        config.timezones[i].timezone_name.clone(),
+        config.timezones[i].pinned,
+    ));
+    match store_config(&config) {
         Ok(_t) => "",
         Err(_e) => {
             eprintln!("Error saving config!");
             return None;
         }
     };
+    pinned
+}
 
-    Some(tz_input)
+// Saves the timezones in the order of `order`, by name. Any not in it keep their place after the rest.
+pub fn reorder_timezones(order: Vec<String>) -> Option<String> {
+    let mut config = match load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+    config.timezones.sort_by_key(|t| {
+        order
+            .iter()
+            .position(|name| *name == t.timezone_name)
+            .unwrap_or(order.len())
+    });
+    match store_config(&config) {
+        Ok(_t) => "",
+        Err(_e) => {
+            eprintln!("Error saving config!");
+            return None;
+        }
+    };
+    Some(format!(
+        "Saved the order of {} timezones",
+        config.timezones.len()
+    ))
 }
 
-pub fn add_sep_to_timezone(tz_input: String) -> Option<String> {
+pub fn import_timezones(path: String, merge: bool, update: bool) -> Option<String> {
     let mut config = match load_config() {
         Ok(t) => t,
         Err(_e) => {
             return None;
         }
     };
-    let mut found = false;
-    for (i, timezone) in config.timezones.clone().into_iter().enumerate() {
-        if tz_input.contains(&timezone.timezone_name) {
-            config.timezones[i].separator = !config.timezones[i].separator;
-            found = true;
-            break;
+    let contents = match fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(_e) => {
+            eprintln!("Error reading {}!", path);
+            return None;
         }
+    };
+    let imported: ImportedDefines = match toml::from_str(&contents) {
+        Ok(t) => t,
+        Err(_e) => {
+            eprintln!("Error parsing {}!", path);
+            return None;
+        }
+    };
+
+    if !merge {
+        config.timezones = [].to_vec();
     }
-    if !found {
-        eprintln!("Timezone not found saved in config!");
-        return None;
+    let (mut added, mut updated, mut skipped) = (0, 0, 0);
+    for timezone in imported.timezones {
+        if timezone.timezone_name.parse::<chrono_tz::Tz>().is_err() {
+            eprintln!("Skipping unknown timezone {}", timezone.timezone_name);
+            skipped += 1;
+            continue;
+        }
+        let contains = saved_list_contains_timezone(&config, &timezone.timezone_name);
+        if contains.1 {
+            if update {
+                let existing = &mut config.timezones[contains.0 as usize];
+                existing.nickname = timezone.nickname;
+                existing.separator = timezone.separator;
+                existing.separator_style = timezone.separator_style;
+                updated += 1;
+            } else {
+                skipped += 1;
+            }
+            continue;
+        }
+        config.timezones.push(SavedTimezones {
+            timezone_name: timezone.timezone_name,
+            nickname: timezone.nickname,
+            separator: timezone.separator,
+            pinned: timezone.pinned,
+            note: timezone.note,
+            separator_style: timezone.separator_style,
+            last_used: Some(now()),
+            extra: timezone.extra,
+        });
+        added += 1;
     }
-    match confy::store(APP_NAME, None, &config) {
+
+    match store_config(&config) {
         Ok(_t) => "",
         Err(_e) => {
             eprintln!("Error saving config!");
             return None;
         }
     };
-    return Some(tz_input);
+    if update {
+        Some(format!(
+            "Added {}, updated {}, skipped {}",
+            added, updated, skipped
+        ))
+    } else {
+        Some(format!("Added {}, skipped {}", added, skipped))
+    }
 }
 
 pub fn remove_timezone(tz_input: String) -> Option<String> {
@@ -223,24 +757,58 @@ pub fn remove_timezone(tz_input: String) -> Option<String> {
             return None;
         }
     };
-    let mut found = false;
-    for (i, timezone) in config.timezones.clone().into_iter().enumerate() {
-        if tz_input.contains(&timezone.timezone_name) {
-            config.timezones.remove(i);
-            found = true;
-            break;
-        }
-    }
-    if !found {
-        eprintln!("Timezone not found saved in config!");
-        return None;
-    }
-    match confy::store(APP_NAME, None, &config) {
+    // Removing can't be undone, so a word from the middle of the name isn't enough.
+    let i = saved_timezone_index(&config, &tz_input, 2)?;
+    let tz_name = config.timezones.remove(i).timezone_name;
+    match store_config(&config) {
         Ok(_t) => "",
         Err(_e) => {
             eprintln!("Error saving config!");
             return None;
         }
     };
-    return Some(tz_input);
+    Some(tz_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_version_defaults_to_zero() {
+        assert_eq!(file_version("version = 7\ntimezones = []").unwrap(), 7);
+        assert_eq!(file_version("timezones = [\"Asia/Tokyo\"]").unwrap(), 0);
+        assert!(file_version("version = ").is_err());
+    }
+
+    // Tests that point `--config` somewhere take turns, since the path is shared.
+    static CONFIG_PATH_LOCK: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn malformed_current_config_is_left_alone() {
+        let _lock = CONFIG_PATH_LOCK.lock().unwrap();
+        let path = env::temp_dir().join(format!("tc-malformed-{}.toml", std::process::id()));
+        let text = "version = 7\n\n[[timezones]]\ntimezone_name = \"Asia/Tokyo\"\nseparator = \"yes\"\nnote = \"keep\"\n";
+        fs::write(&path, text).unwrap();
+        set_config_path(Some(path.display().to_string()));
+        let res = load_config_with(true);
+        set_config_path(None);
+        assert!(matches!(res, Err(ConfyError::BadTomlData(_))));
+        assert_eq!(fs::read_to_string(&path).unwrap(), text);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unreadable_config_is_not_migrated() {
+        let _lock = CONFIG_PATH_LOCK.lock().unwrap();
+        let path = env::temp_dir().join(format!("tc-unreadable-{}", std::process::id()));
+        fs::create_dir(&path).unwrap();
+        set_config_path(Some(path.display().to_string()));
+        let res = load_config_with(true);
+        set_config_path(None);
+        assert!(res.is_err());
+        assert!(!matches!(res, Err(ConfyError::BadTomlData(_))));
+        assert_eq!(fs::read_dir(&path).unwrap().count(), 0);
+        fs::remove_dir(&path).unwrap();
+    }
 }
@@ -1,20 +1,150 @@
 use chrono::format::ParseError;
-use chrono_tz::TZ_VARIANTS;
-use clap::ArgMatches;
+use chrono::{Offset, Utc};
+use chrono_tz::{Tz, TZ_VARIANTS};
+use clap::{crate_name, crate_version, ArgMatches};
 use confy::ConfyError;
-use pancurses::{endwin, initscr, Input};
+use pancurses::{endwin, initscr, noecho, raw, Input};
+use std::io::IsTerminal;
 
+mod cli;
+mod clipboard;
 mod config;
 mod time_helpers;
-mod cli;
 
-fn print_defines_list() -> Result<(), ConfyError> {
-    let config = match config::load_config() {
+// Everything worth pasting into a bug report, each on its own line.
+fn doctor_report() -> String {
+    let mut lines: Vec<(&str, String)> = Vec::new();
+    let (summary, supported) = config::config_summary();
+    lines.push((
+        "Config file",
+        match config::config_file() {
+            Some(t) if t.exists() => t.display().to_string(),
+            Some(t) => format!("{} (not created yet)", t.display()),
+            None => "unknown".to_string(),
+        },
+    ));
+    match summary {
+        Some((version, zones)) => {
+            lines.push((
+                "Config version",
+                format!("{} (this tc supports up to {})", version, supported),
+            ));
+            lines.push(("Saved timezones", zones.to_string()));
+        }
+        None => lines.push(("Config version", "unreadable or missing".to_string())),
+    }
+    lines.push((
+        "Local timezone",
+        match iana_time_zone::get_timezone() {
+            Ok(t) => t,
+            Err(e) => format!("unknown ({})", e),
+        },
+    ));
+    lines.push((
+        "TZ variable",
+        std::env::var("TZ").unwrap_or_else(|_e| "unset".to_string()),
+    ));
+    lines.push((
+        "TC_TIME variable",
+        std::env::var("TC_TIME").unwrap_or_else(|_e| "unset".to_string()),
+    ));
+    lines.push(("tzdata version", chrono_tz::IANA_TZDB_VERSION.to_string()));
+    lines.push(("tc version", crate_version!().to_string()));
+    let term = std::env::var("TERM").unwrap_or_default();
+    let terminal = std::io::stdout().is_terminal();
+    lines.push((
+        "TERM",
+        if term.is_empty() {
+            "unset".to_string()
+        } else {
+            term.clone()
+        },
+    ));
+    lines.push(("Output is a terminal", terminal.to_string()));
+    // What `tc t --curses` needs; tc prints no color, so there is nothing else to check.
+    lines.push((
+        "Curses usable",
+        (terminal && !term.is_empty() && term != "dumb").to_string(),
+    ));
+    lines
+        .iter()
+        .map(|(name, value)| format!("{0: <25} {1}", name, value))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+// Restores the terminal however the curses loop exits, including panics.
+struct CursesGuard;
+
+impl Drop for CursesGuard {
+    fn drop(&mut self) {
+        endwin();
+    }
+}
+
+// Up and down move the highlighted zone, tab and shift+tab pick a different one, enter saves and q
+// or escape gives up. Returns the names in their new order when saved.
+fn reorder_picker(mut names: Vec<String>) -> Option<Vec<String>> {
+    let window = initscr();
+    let _guard = CursesGuard;
+    raw();
+    noecho();
+    window.keypad(true);
+    let mut selected = 0;
+    loop {
+        window.clear();
+        window.addstr("Up/Down to move, Tab/Shift+Tab to select, Enter to save, q to cancel\n\n");
+        for (i, name) in names.iter().enumerate() {
+            let marker = if i == selected { "> " } else { "  " };
+            window.addstr(format!("{}{}\n", marker, name));
+        }
+        match window.getch() {
+            Some(Input::KeyUp) if selected > 0 => {
+                names.swap(selected, selected - 1);
+                selected -= 1;
+            }
+            Some(Input::KeyDown) if selected + 1 < names.len() => {
+                names.swap(selected, selected + 1);
+                selected += 1;
+            }
+            Some(Input::Character('\t')) => selected = (selected + 1) % names.len(),
+            Some(Input::KeyBTab) => selected = (selected + names.len() - 1) % names.len(),
+            Some(Input::KeyEnter) | Some(Input::Character('\n')) | Some(Input::Character('\r')) => {
+                return Some(names)
+            }
+            Some(Input::Character('q'))
+            | Some(Input::Character('\u{1b}'))
+            | Some(Input::Character('\u{3}'))
+            | Some(Input::Character('\u{4}'))
+            | None => return None,
+            Some(_i) => {}
+        }
+    }
+}
+
+// `sort` is `name`, `offset` (current UTC offset) or `added`, which keeps the saved order.
+fn print_defines_list(sort: Option<&str>) -> Result<(), ConfyError> {
+    let mut config = match config::load_config() {
         Ok(t) => t,
         Err(e) => {
             return Err(e);
         }
     };
+    match sort {
+        Some("name") => config
+            .timezones
+            .sort_by(|a, b| a.timezone_name.cmp(&b.timezone_name)),
+        Some("offset") => {
+            let now = Utc::now();
+            config
+                .timezones
+                .sort_by_key(|t| match t.timezone_name.parse::<Tz>() {
+                    Ok(tz) => now.with_timezone(&tz).offset().fix().local_minus_utc(),
+                    Err(_e) => i32::MAX,
+                })
+        }
+        _ => {}
+    }
     for timezone in config.timezones {
         let nick = match timezone.nickname {
             Some(t) => t,
@@ -25,50 +155,200 @@ fn print_defines_list() -> Result<(), ConfyError> {
     Ok(())
 }
 
-fn t_command(sub_matches: Option<&ArgMatches>) -> Option<String> {
-    if sub_matches.is_some() {
-        let matches = sub_matches.unwrap();
-        let timezone_input = match matches.get_one::<String>("timezone") {
-            Some(t) => Some(t.to_string()),
-            None => None,
-        };
-        let time = match matches.get_one::<String>("time") {
-            Some(t) => Some(t.to_string()),
-            None => None,
-        };
-        let output_format = match matches.get_one::<String>("output") {
-            Some(t) => Some(t.to_string()),
-            None => None,
+fn print_nicks_list() -> Result<(), ConfyError> {
+    let config = config::load_config()?;
+    for timezone in config.timezones {
+        if let Some(nick) = timezone.nickname {
+            println!("{} -> {}", nick, timezone.timezone_name);
+        }
+    }
+    Ok(())
+}
+
+// For `--show` on the commands that change the list.
+fn show_list(sub_matches: &ArgMatches) {
+    if sub_matches.get_flag("show") {
+        println!();
+        let _ = print_defines_list(None);
+    }
+}
+
+// `TC_TIME` stands in for a missing time argument, so a script can set the time once.
+fn env_time() -> Option<String> {
+    std::env::var("TC_TIME")
+        .ok()
+        .filter(|t| !t.trim().is_empty())
+}
+
+fn t_command(
+    ctx: &time_helpers::RenderContext,
+    sub_matches: Option<&ArgMatches>,
+) -> Option<String> {
+    match sub_matches {
+        Some(matches) => {
+            let (options, times) = t_options(matches)?;
+            time_helpers::render(ctx, &options, &times)
+        }
+        None => {
+            let times: Vec<String> = env_time().into_iter().collect();
+            time_helpers::render(ctx, &time_helpers::RenderOptions::default(), &times)
+        }
+    }
+}
+
+fn t_options(matches: &ArgMatches) -> Option<(time_helpers::RenderOptions, Vec<String>)> {
+    let mut options = time_helpers::RenderOptions {
+        timezone_input: matches.get_one::<String>("timezone").cloned(),
+        time: None,
+        output_format: matches.get_one::<String>("output").cloned(),
+        pretty_width: match matches.get_one::<usize>("pretty_width") {
+            Some(t) => *t,
+            None => 25,
+        },
+        oneline_separator: match matches.get_one::<String>("oneline_separator") {
+            Some(t) => t.to_string(),
+            None => " | ".to_string(),
+        },
+        reverse: matches.get_flag("reverse"),
+        header_format: matches.get_one::<String>("header_format").cloned(),
+        subsec: matches.get_flag("subsec"),
+        show_date: matches.get_flag("show_date"),
+        delta: matches.get_flag("delta"),
+        anchor: matches.get_one::<String>("anchor").cloned(),
+        sort: matches.get_one::<String>("sort").cloned(),
+        truncate_names: matches.get_flag("truncate_names"),
+        header_zone_name: matches.get_one::<String>("header_zone_name").cloned(),
+        template: matches.get_one::<String>("template").cloned(),
+        template_header: None,
+        template_footer: None,
+        wrap: matches.get_one::<usize>("wrap").copied(),
+        cross_day_only: matches.get_flag("cross_day_only"),
+        same_day_only: matches.get_flag("same_day_only"),
+        highlight: matches.get_one::<String>("highlight").cloned(),
+        min_width: matches.get_one::<usize>("min_width").copied(),
+        max_width: matches.get_one::<usize>("max_width").copied(),
+        bare_epoch: matches.get_one::<String>("bare_number").map(|t| t.as_str()) == Some("epoch"),
+        group_by: matches.get_one::<String>("group_by").cloned(),
+        columns: matches
+            .get_one::<String>("columns")
+            .map(|t| t.split(',').map(|c| c.trim().to_string()).collect()),
+    };
+    if matches.get_flag("json_min") {
+        if options.output_format.as_deref() != Some("json_pretty") {
+            options.output_format = Some("json".to_string());
+        }
+        options.columns = Some(
+            time_helpers::MIN_JSON_COLUMNS
+                .iter()
+                .map(|t| t.to_string())
+                .collect(),
+        );
+    }
+    if let Some(path) = matches.get_one::<String>("format_file") {
+        let text = match std::fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => {
+                eprintln!("Couldn't read {}: {}", path, e);
+                return None;
+            }
         };
-        return time_helpers::render_time(timezone_input, time, output_format);
-    } else {
-        return time_helpers::render_time(None, None, None);
+        let (header, row, footer) = time_helpers::parse_format_file(&text)?;
+        options.output_format = Some("template".to_string());
+        options.template = Some(row);
+        options.template_header = header;
+        options.template_footer = footer;
     }
+    let times: Vec<String> = match matches.get_many::<String>("time") {
+        Some(t) => t.cloned().collect(),
+        None => env_time().into_iter().collect(),
+    };
+    Some((options, times))
 }
 
 fn main() -> Result<(), ParseError> {
     let matches = cli::cli().get_matches();
+    config::set_no_migrate(matches.get_flag("no_migrate"));
+    config::set_strict(matches.get_flag("strict"));
+    config::set_no_write(matches.get_flag("no_write"));
+    config::set_verbose(matches.get_flag("verbose"));
+    config::set_config_path(matches.get_one::<String>("config").cloned());
 
     match matches.subcommand() {
         Some(("u", sub_matches)) => {
             let time = sub_matches.get_one::<String>("time");
 
-            let input: Option<String> = match time {
-                Some(t) => Some(t.to_string()),
+            let input: Option<String> = time.cloned().or_else(env_time);
+
+            let from_zone = sub_matches.get_one::<String>("from_zone").cloned();
+            let prefer = match sub_matches.get_one::<String>("prefer").map(|t| t.as_str()) {
+                Some("later") => Some(time_helpers::Prefer::Later),
+                Some(_) => Some(time_helpers::Prefer::Earlier),
                 None => None,
             };
 
-            let timestamp = time_helpers::get_unix_timestamp(input);
+            let bare_epoch = sub_matches
+                .get_one::<String>("bare_number")
+                .map(|t| t.as_str())
+                == Some("epoch");
+            let timestamps: Vec<(String, i64)> = match sub_matches.get_one::<String>("file") {
+                Some(path) => {
+                    match time_helpers::batch_unix_timestamps(
+                        path.clone(),
+                        from_zone,
+                        prefer,
+                        bare_epoch,
+                    ) {
+                        Some(t) => t,
+                        None => return Ok(()),
+                    }
+                }
+                None => match time_helpers::get_unix_timestamp(
+                    input.clone(),
+                    from_zone,
+                    prefer,
+                    bare_epoch,
+                ) {
+                    Some(t) => [(input.unwrap_or_default(), t)].to_vec(),
+                    None => return Ok(()),
+                },
+            };
 
             let discord_ts = match sub_matches.get_one::<bool>("discord") {
                 Some(t) => *t,
                 None => false,
             };
+            // `--style` picks the Discord style, so it implies `--discord`.
+            let style = sub_matches.get_one::<String>("style");
+            let discord_ts = discord_ts || style.is_some();
+            let style = style.map(|t| t.as_str()).unwrap_or("t");
+            let preview = sub_matches.get_flag("preview");
+            let csv = sub_matches.get_flag("csv");
 
-            if discord_ts {
-                println!("<t:{}:t>", timestamp);
-            } else {
-                println!("{}", timestamp);
+            let mut lines: Vec<String> = Vec::new();
+            if csv {
+                lines.push("Input,Timestamp".to_string());
+            }
+            for (line, timestamp) in timestamps {
+                let formatted = if discord_ts {
+                    format!("<t:{}:{}>", timestamp, style)
+                } else {
+                    timestamp.to_string()
+                };
+                if csv {
+                    lines.push(format!("{},{}", time_helpers::csv_field(&line), formatted));
+                } else {
+                    lines.push(formatted);
+                }
+                if preview && discord_ts {
+                    if let Some(t) = time_helpers::discord_preview(timestamp, style) {
+                        lines.push(t);
+                    }
+                }
+            }
+            let output = lines.join("\n");
+            println!("{}", output);
+            if sub_matches.get_flag("copy") {
+                clipboard::copy(&output);
             }
         }
         Some(("d", sub_matches)) => match sub_matches.subcommand() {
@@ -81,11 +361,36 @@ fn main() -> Result<(), ParseError> {
                     }
                 };
 
-                let res = config::add_timezone(tz_input.clone());
+                let nickname = sub_matches_add.get_one::<String>("nick").cloned();
+                let res = config::add_timezone(tz_input.clone(), nickname.clone());
+                match (res, nickname) {
+                    (Some(t), Some(nick)) if !nick.is_empty() => {
+                        println!(
+                            "Added timezone {} as {}",
+                            t,
+                            config::normalize_nickname(&nick)
+                        )
+                    }
+                    (Some(t), _) => println!("Added timezone {}", t),
+                    (None, _) => return Ok(()),
+                }
+                show_list(sub_matches_add);
+            }
+            Some(("add-local", sub_matches_add_local)) => {
+                let tz_name = match iana_time_zone::get_timezone() {
+                    Ok(t) => t,
+                    Err(_e) => {
+                        eprintln!("Couldn't detect the system timezone! Find it with `tc d suggest <city>` and add it with `tc d add <zone>`.");
+                        return Ok(());
+                    }
+                };
+
+                let res = config::add_timezone(tz_name, None);
                 match res {
                     Some(t) => println!("Added timezone {}", t),
                     None => return Ok(()),
                 }
+                show_list(sub_matches_add_local);
             }
             Some(("nick", sub_matches_nick)) => {
                 let tz_input = match sub_matches_nick.get_one::<String>("timezone") {
@@ -96,19 +401,55 @@ fn main() -> Result<(), ParseError> {
                     }
                 };
 
-                let nickname: String;
-
                 let nick: Option<String> = sub_matches_nick.get_one::<String>("nickname").cloned();
-                match nick {
-                    Some(t) => nickname = t,
-                    None => nickname = "".to_string(),
-                }
+                let nickname = match nick {
+                    Some(t) => t,
+                    None => "".to_string(),
+                };
 
                 let res = config::add_nick_to_timezone(tz_input.clone(), nickname);
                 match res {
                     Some(t) => println!("Added nickname to {}", t),
                     None => return Ok(()),
                 }
+                show_list(sub_matches_nick);
+            }
+            Some(("note", sub_matches_note)) => {
+                let tz_input = match sub_matches_note.get_one::<String>("timezone") {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("Timezone not specified!");
+                        return Ok(());
+                    }
+                };
+
+                let note = match sub_matches_note.get_one::<String>("note") {
+                    Some(t) => t.to_string(),
+                    None => "".to_string(),
+                };
+
+                let res = config::add_note_to_timezone(tz_input.clone(), note);
+                match res {
+                    Some(t) => println!("Added note to {}", t),
+                    None => return Ok(()),
+                }
+                show_list(sub_matches_note);
+            }
+            Some(("pin", sub_matches_pin)) => {
+                let tz_input = match sub_matches_pin.get_one::<String>("timezone") {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("Timezone not specified!");
+                        return Ok(());
+                    }
+                };
+
+                match config::toggle_pin_timezone(tz_input.clone()) {
+                    Some((name, true)) => println!("Pinned {}", name),
+                    Some((name, false)) => println!("Unpinned {}", name),
+                    None => return Ok(()),
+                }
+                show_list(sub_matches_pin);
             }
             Some(("sep", sub_matches_sep)) => {
                 let tz_input = match sub_matches_sep.get_one::<String>("timezone") {
@@ -119,14 +460,122 @@ fn main() -> Result<(), ParseError> {
                     }
                 };
 
-                let res = config::add_sep_to_timezone(tz_input.clone());
+                let style = sub_matches_sep.get_one::<String>("style").cloned();
+                let res = config::add_sep_to_timezone(tz_input.clone(), style);
                 match res {
                     Some(t) => println!("Added separator after {}", t),
                     None => return Ok(()),
                 }
+                show_list(sub_matches_sep);
             }
-            Some(("list", _)) => {
-                match print_defines_list() {
+            Some(("import", sub_matches_import)) => {
+                let path = match sub_matches_import.get_one::<String>("path") {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("Path not specified!");
+                        return Ok(());
+                    }
+                };
+
+                match config::import_timezones(
+                    path.clone(),
+                    sub_matches_import.get_flag("merge"),
+                    sub_matches_import.get_flag("update"),
+                ) {
+                    Some(t) => println!("{}", t),
+                    None => return Ok(()),
+                }
+                show_list(sub_matches_import);
+            }
+            Some(("info", sub_matches_info)) => {
+                let tz_input = match sub_matches_info.get_one::<String>("timezone") {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("Timezone not specified!");
+                        return Ok(());
+                    }
+                };
+
+                match time_helpers::describe_timezone(tz_input.clone()) {
+                    Some(t) => println!("{}", t),
+                    None => return Ok(()),
+                }
+            }
+            Some(("list", sub_matches_list)) => {
+                let sort = sub_matches_list
+                    .get_one::<String>("sort")
+                    .map(|t| t.as_str());
+                if sub_matches_list.get_flag("json") {
+                    match config::load_config() {
+                        Ok(t) => println!("{}", serde_json::to_string(&t).unwrap()),
+                        Err(_e) => return Ok(()),
+                    }
+                    return Ok(());
+                }
+                match print_defines_list(sort) {
+                    Ok(t) => return Ok(t),
+                    Err(_e) => return Ok(()),
+                };
+            }
+            Some(("reorder", sub_matches_reorder)) => {
+                let names: Vec<String> = match config::load_config() {
+                    Ok(t) => t.timezones.into_iter().map(|t| t.timezone_name).collect(),
+                    Err(_e) => return Ok(()),
+                };
+                if names.is_empty() {
+                    eprintln!("No timezones to reorder!");
+                    return Ok(());
+                }
+                let order = match reorder_picker(names) {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("Reorder cancelled.");
+                        return Ok(());
+                    }
+                };
+                match config::reorder_timezones(order) {
+                    Some(t) => println!("{}", t),
+                    None => return Ok(()),
+                }
+                show_list(sub_matches_reorder);
+            }
+            Some(("prune", sub_matches_prune)) => {
+                let days = *sub_matches_prune.get_one::<u64>("older_than").unwrap();
+                let stale = match config::stale_timezones(days) {
+                    Some(t) => t,
+                    None => return Ok(()),
+                };
+                if stale.is_empty() {
+                    println!("No timezones unused for {} days.", days);
+                    return Ok(());
+                }
+                for (name, age) in &stale {
+                    println!("{0: <25} last used {1} days ago", name, age);
+                }
+                if !sub_matches_prune.get_flag("yes") {
+                    eprint!("Remove these {} timezones? [y/N] ", stale.len());
+                    let mut answer = String::new();
+                    if std::io::stdin().read_line(&mut answer).is_err()
+                        || !answer.trim().eq_ignore_ascii_case("y")
+                    {
+                        eprintln!("Nothing removed.");
+                        return Ok(());
+                    }
+                }
+                for (name, _) in stale {
+                    match config::remove_timezone(name) {
+                        Some(t) => println!("Removed timezone {}", t),
+                        None => return Ok(()),
+                    }
+                }
+                show_list(sub_matches_prune);
+            }
+            Some(("migrate", _)) => match config::migrate_config() {
+                Some(t) => println!("{}", t),
+                None => return Ok(()),
+            },
+            Some(("nicks", _)) => {
+                match print_nicks_list() {
                     Ok(t) => return Ok(t),
                     Err(_e) => return Ok(()),
                 };
@@ -145,8 +594,37 @@ fn main() -> Result<(), ParseError> {
                     Some(t) => println!("Removed timezone {}", t),
                     None => return Ok(()),
                 }
+                show_list(sub_matches_remove);
             }
-            Some(("list-available", _)) => {
+            Some(("suggest", sub_matches_suggest)) => {
+                let city = match sub_matches_suggest.get_one::<String>("city") {
+                    Some(t) => t,
+                    None => {
+                        eprintln!("City not specified!");
+                        return Ok(());
+                    }
+                };
+
+                match time_helpers::suggest_timezones(city.clone()) {
+                    Some(t) => println!("{}", t),
+                    None => return Ok(()),
+                }
+            }
+            Some(("list-available", sub_matches_available)) => {
+                if let Some(offset) = sub_matches_available.get_one::<String>("offset") {
+                    match time_helpers::zones_at_offset(offset.clone()) {
+                        Some(t) => println!("{}", t),
+                        None => return Ok(()),
+                    }
+                    return Ok(());
+                }
+                if let Some(country) = sub_matches_available.get_one::<String>("country") {
+                    match time_helpers::zones_in_country(country.clone()) {
+                        Some(t) => println!("{}", t),
+                        None => return Ok(()),
+                    }
+                    return Ok(());
+                }
                 for timezone in TZ_VARIANTS {
                     println!("{}", timezone.name());
                 }
@@ -155,36 +633,139 @@ fn main() -> Result<(), ParseError> {
                 eprintln!("Invalid Command!");
             }
             None => {
-                match print_defines_list() {
+                match print_defines_list(None) {
                     Ok(t) => return Ok(t),
                     Err(_e) => return Ok(()),
                 };
             }
         },
+        Some(("dst", sub_matches)) => {
+            let tz_input = match sub_matches.get_one::<String>("timezone") {
+                Some(t) => t,
+                None => {
+                    eprintln!("Timezone not specified!");
+                    return Ok(());
+                }
+            };
+
+            match time_helpers::dst_transitions(tz_input.clone()) {
+                Some(t) => print!("{}", t),
+                None => return Ok(()),
+            }
+        }
+        Some(("gap", sub_matches)) => {
+            let (from, to) = match (
+                sub_matches.get_one::<String>("from"),
+                sub_matches.get_one::<String>("to"),
+            ) {
+                (Some(a), Some(b)) => (a, b),
+                _ => {
+                    eprintln!("Two timezones are needed!");
+                    return Ok(());
+                }
+            };
+
+            match time_helpers::zone_gap(from.clone(), to.clone()) {
+                Some(t) => println!("{}", t),
+                None => return Ok(()),
+            }
+        }
+        Some(("schedule", sub_matches)) => {
+            let ctx = match time_helpers::RenderContext::load() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+            let options = time_helpers::RenderOptions {
+                timezone_input: sub_matches.get_one::<String>("timezone").cloned(),
+                time: sub_matches.get_one::<String>("time").cloned(),
+                ..time_helpers::RenderOptions::default()
+            };
+            let json = sub_matches.get_one::<String>("output").map(|t| t.as_str()) == Some("json");
+            match time_helpers::render_schedule(&ctx, &options, json) {
+                Some(t) => println!("{}", t),
+                None => return Ok(()),
+            }
+        }
+        Some(("cron", sub_matches)) => {
+            let ctx = match time_helpers::RenderContext::load() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+            let options = time_helpers::RenderOptions {
+                timezone_input: sub_matches.get_one::<String>("timezone").cloned(),
+                time: sub_matches.get_one::<String>("time").cloned(),
+                ..time_helpers::RenderOptions::default()
+            };
+            match time_helpers::render_cron(&ctx, &options) {
+                Some(t) => println!("{}", t),
+                None => return Ok(()),
+            }
+        }
+        Some(("doctor", _)) => println!("{}", doctor_report()),
+        Some(("version", sub_matches)) => {
+            if sub_matches.get_flag("json") {
+                let version = serde_json::json!({
+                    "name": crate_name!(),
+                    "version": crate_version!(),
+                    "tzdata": chrono_tz::IANA_TZDB_VERSION,
+                });
+                println!("{}", version);
+            } else if sub_matches.get_flag("short") {
+                println!("{}", crate_version!());
+            } else {
+                println!("{} {}", crate_name!(), crate_version!());
+            }
+        }
         Some(("t", sub_matches)) => {
+            let ctx = match time_helpers::RenderContext::load() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+            let ctx = match sub_matches.get_one::<String>("zones") {
+                Some(zones) => match ctx.with_zones(zones) {
+                    Some(t) => t,
+                    None => return Ok(()),
+                },
+                None => ctx,
+            };
             let curses = match sub_matches.get_one::<bool>("curses") {
                 Some(t) => *t,
                 None => false,
             };
             if curses {
                 let window = initscr();
+                let _guard = CursesGuard;
+                // Raw mode hands Ctrl-C/Ctrl-D to us so the guard still restores the terminal.
+                raw();
                 window.nodelay(true);
                 loop {
                     window.clear();
-                    match t_command(Some(sub_matches)) {
+                    match t_command(&ctx, Some(sub_matches)) {
                         Some(t) => window.addstr(t),
                         None => break,
                     };
                     match window.getch() {
                         Some(Input::KeyCancel) => break,
+                        Some(Input::Character('\u{3}')) | Some(Input::Character('\u{4}')) => break,
                         Some(_i) => (),
                         None => (),
                     };
                 }
-                endwin();
             } else {
-                match t_command(Some(sub_matches)) {
-                    Some(t) => println!("{}", t),
+                let (options, times) = match t_options(sub_matches) {
+                    Some(t) => t,
+                    None => return Ok(()),
+                };
+                match time_helpers::render(&ctx, &options, &times) {
+                    Some(t) => {
+                        println!("{}", t);
+                        if sub_matches.get_flag("copy") {
+                            clipboard::copy(&t);
+                        }
+                        config::touch_timezones(&time_helpers::saved_pivots(
+                            &ctx, &options, &times,
+                        ));
+                    }
                     None => return Ok(()),
                 };
             }
@@ -193,7 +774,11 @@ fn main() -> Result<(), ParseError> {
             eprintln!("Invalid Command!");
         }
         None => {
-            match t_command(None) {
+            let ctx = match time_helpers::RenderContext::load() {
+                Some(t) => t,
+                None => return Ok(()),
+            };
+            match t_command(&ctx, None) {
                 Some(t) => println!("{}", t),
                 None => return Ok(()),
             };
@@ -1,18 +1,44 @@
 use chrono::format::ParseError;
-use chrono::{offset, DateTime, Datelike, Local, NaiveTime, TimeZone, Timelike};
+use chrono::{
+    offset, DateTime, Datelike, Duration, FixedOffset, Local, LocalResult, NaiveDateTime,
+    NaiveTime, Offset, TimeZone, Timelike,
+};
 use chrono_tz::Tz;
 use serde_derive::Serialize;
+use std::env;
+
+use super::RenderOptions;
+use crate::config;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Prefer {
+    Earlier,
+    Later,
+}
 
 #[derive(PartialEq)]
 pub enum CurTimeKind {
     Local,
     Tz,
+    Fixed,
 }
 
 pub struct CurTime {
     pub kind: CurTimeKind,
     pub local_time: Option<DateTime<Local>>,
     pub tz_time: Option<DateTime<Tz>>,
+    pub fixed_time: Option<DateTime<FixedOffset>>,
+}
+
+impl CurTime {
+    // The same instant whichever kind it is, keeping the wall-clock offset it was entered in.
+    pub fn as_fixed(&self) -> DateTime<FixedOffset> {
+        match self.kind {
+            CurTimeKind::Local => self.local_time.unwrap().fixed_offset(),
+            CurTimeKind::Tz => self.tz_time.unwrap().fixed_offset(),
+            CurTimeKind::Fixed => self.fixed_time.unwrap(),
+        }
+    }
 }
 
 #[derive(Serialize, Clone)]
@@ -20,17 +46,102 @@ pub struct OutputTime {
     pub timezone_name: String,
     pub timezone_nickname: Option<String>,
     pub displayed_name: String,
-    pub day_offset: u32,
+    pub day_offset: i32,
     pub day_offset_str: String,
     pub timestamp: i64,
+    pub epoch: i64,
     pub timestring: String,
     pub separator: bool,
+    pub separator_style: Option<String>,
+    pub pinned: bool,
+    pub is_dst: bool,
+    pub delta: i32,
+    pub utc_offset_seconds: i32,
+    pub note: Option<String>,
+    pub iso_week: u32,
+    pub ordinal: u32,
+}
+
+#[derive(Serialize)]
+pub struct ScheduleEntry {
+    pub timezone_name: String,
+    pub timezone_nickname: Option<String>,
+    pub date: String,
+    pub weekday: String,
+    pub time: String,
+    pub sentence: String,
 }
 
-pub fn tz_offset_from_local_time(
+// Field names of `OutputTime`, which is what `--columns` picks from.
+pub const OUTPUT_COLUMNS: [&str; 17] = [
+    "timezone_name",
+    "timezone_nickname",
+    "displayed_name",
+    "day_offset",
+    "day_offset_str",
+    "timestamp",
+    "epoch",
+    "timestring",
+    "separator",
+    "separator_style",
+    "pinned",
+    "is_dst",
+    "delta",
+    "utc_offset_seconds",
+    "note",
+    "iso_week",
+    "ordinal",
+];
+
+// What `--json-min` keeps, leaving out fields that are only there for display.
+pub const MIN_JSON_COLUMNS: [&str; 4] = [
+    "timezone_name",
+    "timestamp",
+    "utc_offset_seconds",
+    "day_offset",
+];
+
+#[derive(Serialize)]
+pub struct OutputSlot {
+    pub time: String,
+    pub zones: serde_json::Value,
+}
+
+#[derive(Serialize)]
+pub struct OutputPivot {
+    pub timezone: String,
+    pub times: serde_json::Value,
+}
+
+// Each time as a json object, cut down to `columns` when given.
+pub fn zones_json(tz_list: &[OutputTime], columns: Option<&[String]>) -> serde_json::Value {
+    let value = serde_json::to_value(tz_list).unwrap();
+    let columns = match columns {
+        Some(t) => t,
+        None => return value,
+    };
+    let zones = match value {
+        serde_json::Value::Array(t) => t,
+        _ => return value,
+    };
+    serde_json::Value::Array(
+        zones
+            .into_iter()
+            .map(|zone| {
+                let mut res = serde_json::Map::new();
+                for column in columns {
+                    res.insert(column.to_string(), zone[column.as_str()].clone());
+                }
+                serde_json::Value::Object(res)
+            })
+            .collect(),
+    )
+}
+
+pub fn tz_offset_from_local_time<T: TimeZone>(
     time: NaiveTime,
     now: DateTime<Local>,
-    tz: Option<Tz>,
+    tz: Option<T>,
 ) -> NaiveTime {
     match tz {
         Some(t) => {
@@ -55,73 +166,647 @@ pub fn tz_offset_from_local_time(
 pub fn get_comparison_date_time(
     time_option: Option<String>,
     tz: Option<Tz>,
-) -> Result<CurTime, ParseError> {
+    fixed: Option<FixedOffset>,
+    prefer: Option<Prefer>,
+) -> Result<CurTime, String> {
     let now = offset::Local::now();
+    let default_time = || {
+        let now_time = NaiveTime::from_hms_opt(now.hour(), now.minute(), now.second()).unwrap();
+        match fixed {
+            Some(f) => tz_offset_from_local_time(now_time, now, Some(f)),
+            None => tz_offset_from_local_time(now_time, now, tz),
+        }
+    };
 
-    let time = match time_option {
-        Some(t) => {
-            let collection: Vec<&str> = t.split(":").collect();
-            match collection.len() {
-                3 => NaiveTime::parse_from_str(t.as_str(), "%H:%M:%S")?,
-                2 => NaiveTime::parse_from_str(t.as_str(), "%H:%M")?,
-                1 => {
-                    let newstring = collection[0].to_string() + ":00";
-                    NaiveTime::parse_from_str(&newstring, "%H:%M")?
-                }
-                _ => tz_offset_from_local_time(
-                    NaiveTime::from_hms_opt(now.hour(), now.minute(), now.second()).unwrap(),
-                    now,
-                    tz,
-                ),
+    let time = match &time_option {
+        Some(raw) => match parse_time_of_day(raw) {
+            Ok(t) => t.unwrap_or_else(default_time),
+            Err(_e) => {
+                return Err(format!(
+                    "Couldn't parse time {:?}: expected HH:MM, HH:MM:SS, or 12-hour like 3:30pm",
+                    raw.trim()
+                ))
             }
-        } // Handle if not okay.
-        None => tz_offset_from_local_time(
-            NaiveTime::from_hms_opt(now.hour(), now.minute(), now.second()).unwrap(),
-            now,
-            tz,
-        ),
+        },
+        None => default_time(),
     };
 
     let mut res = CurTime {
         kind: CurTimeKind::Local,
         local_time: None,
         tz_time: None,
+        fixed_time: None,
     };
 
+    let mut naive = now.date_naive().and_time(time);
+    if time_option.as_deref().is_some_and(is_end_of_day) {
+        naive += Duration::days(1);
+    }
+
+    if let Some(f) = fixed {
+        res.fixed_time = Some(resolve_local(&f, naive, prefer));
+        res.kind = CurTimeKind::Fixed;
+        return Ok(res);
+    }
+
     match tz {
         Some(t) => {
-            res.tz_time = Some(
-                t.with_ymd_and_hms(
-                    now.year(),
-                    now.month(),
-                    now.day(),
-                    time.hour(),
-                    time.minute(),
-                    time.second(),
-                )
-                .unwrap(),
-            );
+            res.tz_time = Some(resolve_local(&t, naive, prefer));
             res.kind = CurTimeKind::Tz;
         }
         None => {
-            res.local_time = Some(
-                offset::Local
-                    .with_ymd_and_hms(
-                        now.year(),
-                        now.month(),
-                        now.day(),
-                        time.hour(),
-                        time.minute(),
-                        time.second(),
-                    )
-                    .unwrap(),
-            );
+            res.local_time = Some(resolve_local(&offset::Local, naive, prefer));
         }
     }
 
     Ok(res)
 }
 
-pub fn convert_date_to_timestamp(year: i32, ordinal: u32) -> u32 {
-    ordinal + ((year - 1970) * 365) as u32
+// A timestamp typed as `@1700000000`, or as a bare `1700000000` when `--bare-number epoch` says a
+// lone number is one.
+pub fn input_epoch(input: &str, bare_epoch: bool) -> Option<i64> {
+    let trimmed = input.trim();
+    if bare_epoch && !trimmed.is_empty() && trimmed.chars().all(|c| c.is_ascii_digit()) {
+        return trimmed.parse().ok();
+    }
+    parse_epoch(input)
+}
+
+// `@1700000000` or `@1700000000.25`, rounded down to the second.
+pub fn parse_epoch(input: &str) -> Option<i64> {
+    let rest = input.trim().strip_prefix('@')?;
+    let (seconds, fraction) = match rest.split_once('.') {
+        Some((s, f)) => (s, f),
+        None => (rest, ""),
+    };
+    if !fraction.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let negative = seconds.starts_with('-');
+    let seconds: i64 = seconds.parse().ok()?;
+    // Towards negative infinity, so `@-1.5` is -2 like any other floor.
+    if negative && fraction.chars().any(|c| c != '0') {
+        Some(seconds - 1)
+    } else {
+        Some(seconds)
+    }
+}
+
+// `24:00` (or `24:00:00`) is the midnight that ends the day, as used by transit and broadcast
+// schedules.
+pub fn is_end_of_day(raw: &str) -> bool {
+    let parts: Vec<&str> = raw.trim().split(':').map(|part| part.trim()).collect();
+    matches!(parts.as_slice(), ["24", "00"] | ["24", "00", "00"])
+}
+
+// `Ok(None)` means "now": `@now`, or input that isn't shaped like a time at all.
+pub fn parse_time_of_day(raw: &str) -> Result<Option<NaiveTime>, ParseError> {
+    config::verbose!("Parsing time {:?}", raw);
+    match raw.trim().to_lowercase().as_str() {
+        "@now" => return Ok(None),
+        "noon" => return Ok(NaiveTime::from_hms_opt(12, 0, 0)),
+        "midnight" | "@today" => return Ok(NaiveTime::from_hms_opt(0, 0, 0)),
+        _ => {}
+    }
+    // The caller moves the date on a day, see `is_end_of_day`.
+    if is_end_of_day(raw) {
+        return Ok(NaiveTime::from_hms_opt(0, 0, 0));
+    }
+    // `s55800` is seconds since midnight. Going through the parser keeps 86400 and up an error.
+    if let Some(secs) = raw
+        .trim()
+        .strip_prefix('s')
+        .and_then(|t| t.parse::<u64>().ok())
+    {
+        let t = format!("{}:{}:{}", secs / 3600, secs / 60 % 60, secs % 60);
+        return Ok(Some(NaiveTime::parse_from_str(&t, "%H:%M:%S")?));
+    }
+    // `3pm`, `11 am` and `3:30pm` are 12-hour times. `%I` only takes hours 1 to 12, so `12am` is
+    // midnight, `12pm` is noon and `15pm` is an error.
+    let lower = raw.trim().to_lowercase();
+    if let Some((rest, suffix)) = lower
+        .strip_suffix("am")
+        .map(|t| (t, "AM"))
+        .or_else(|| lower.strip_suffix("pm").map(|t| (t, "PM")))
+    {
+        let time = match parse_time_of_day(rest)? {
+            Some(t) => t,
+            None => return Ok(None),
+        };
+        let t = format!("{}:{} {}", time.hour(), time.format("%M:%S%.f"), suffix);
+        return Ok(Some(NaiveTime::parse_from_str(&t, "%I:%M:%S%.f %p")?));
+    }
+    // Pasted times often pick up padding like ` 15 : 30\n`, so trim each component.
+    let t = raw
+        .trim()
+        .split(':')
+        .map(|part| part.trim())
+        .collect::<Vec<&str>>()
+        .join(":");
+    // A trailing colon is a typo rather than an empty field, so `15:` is `15:00`.
+    let t = t.strip_suffix(':').unwrap_or(&t).to_string();
+    let collection: Vec<&str> = t.split(':').collect();
+    let time = match collection.len() {
+        3 => NaiveTime::parse_from_str(t.as_str(), "%H:%M:%S%.f")?,
+        2 => NaiveTime::parse_from_str(t.as_str(), "%H:%M")?,
+        1 => {
+            let newstring = collection[0].to_string() + ":00";
+            NaiveTime::parse_from_str(&newstring, "%H:%M")?
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(time))
+}
+
+pub fn format_time(time: NaiveTime, subsec: bool) -> String {
+    if subsec {
+        time.format("%H:%M:%S%.3f").to_string()
+    } else {
+        time.format("%H:%M:%S").to_string()
+    }
+}
+
+// Wall-clock times repeated or skipped by a DST change map to two candidate instants. `prefer`
+// picks between them by instant; without it the earlier one is used and a warning printed.
+pub fn resolve_local<T: TimeZone>(
+    tz: &T,
+    naive: NaiveDateTime,
+    prefer: Option<Prefer>,
+) -> DateTime<T> {
+    let (earlier, later) = match tz.from_local_datetime(&naive) {
+        LocalResult::Single(t) => return t,
+        LocalResult::Ambiguous(a, b) => (a, b),
+        LocalResult::None => {
+            // Read the skipped time with the offsets either side of the gap.
+            let offset_near = |n: NaiveDateTime| match tz.offset_from_local_datetime(&n).earliest()
+            {
+                Some(t) => t.fix(),
+                None => tz.offset_from_utc_datetime(&n).fix(),
+            };
+            let a = tz.from_utc_datetime(&(naive - offset_near(naive - Duration::days(1))));
+            let b = tz.from_utc_datetime(&(naive - offset_near(naive + Duration::days(1))));
+            if a <= b {
+                (a, b)
+            } else {
+                (b, a)
+            }
+        }
+    };
+    match prefer {
+        Some(Prefer::Earlier) => earlier,
+        Some(Prefer::Later) => later,
+        None => {
+            eprintln!(
+                "{} is ambiguous or skipped in this timezone, using the earlier instant.",
+                naive
+            );
+            earlier
+        }
+    }
+}
+
+// Lower is a better match. City (the part after the last `/`) and whole-word matches beat an
+// arbitrary substring of the full name, so `york` lands on `America/New_York`.
+pub fn match_rank(tz_name: &str, term: &str) -> Option<u8> {
+    let name = tz_name.to_lowercase();
+    let term = term.trim().to_lowercase().replace(' ', "_");
+    if term.is_empty() {
+        return None;
+    }
+    let city = match name.rsplit_once('/') {
+        Some((_, t)) => t,
+        None => name.as_str(),
+    };
+    let is_word = |haystack: &str| haystack.split(['/', '_', '-']).any(|w| w == term);
+
+    if name == term {
+        Some(0)
+    } else if city == term {
+        Some(1)
+    } else if is_word(city) {
+        Some(2)
+    } else if is_word(&name) {
+        Some(3)
+    } else if city.contains(&term) {
+        Some(4)
+    } else if name.contains(&term) {
+        Some(5)
+    } else {
+        None
+    }
+}
+
+// csv or tsv with a header row of column names, for `--columns`.
+pub fn columns_table(
+    tz_list: &[OutputTime],
+    columns: &[String],
+    delimiter: &str,
+    field: fn(&str) -> String,
+) -> String {
+    let mut output = format!("{}\n", columns.join(delimiter));
+    let zones = match zones_json(tz_list, Some(columns)) {
+        serde_json::Value::Array(t) => t,
+        _ => return output,
+    };
+    for zone in zones {
+        let fields: Vec<String> = columns
+            .iter()
+            .map(|column| match &zone[column.as_str()] {
+                serde_json::Value::String(t) => field(t),
+                t => t.to_string(),
+            })
+            .collect();
+        output += &format!("{}\n", fields.join(delimiter));
+    }
+    output
+}
+
+// Names inside `{}` in a `--template`, in order.
+pub fn template_placeholders(template: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let after = &rest[start + 1..];
+        match after.find('}') {
+            Some(end) => {
+                names.push(after[..end].to_string());
+                rest = &after[end + 1..];
+            }
+            None => break,
+        }
+    }
+    names
+}
+
+// One `--template` row, with each `{field}` replaced by that field of `zone`. Missing values like a
+// zone with no nickname come out empty.
+pub fn fill_template(template: &str, zone: &serde_json::Value) -> String {
+    let mut row = template.to_string();
+    for name in template_placeholders(template) {
+        let value = match &zone[name.as_str()] {
+            serde_json::Value::String(t) => t.to_string(),
+            serde_json::Value::Null => "".to_string(),
+            t => t.to_string(),
+        };
+        row = row.replace(&format!("{{{}}}", name), &value);
+    }
+    row
+}
+
+// A line of pretty output, plus the separator line under it when the zone has one. The last row
+// of a list never gets one, since there is nothing after it to separate.
+pub fn pretty_row(
+    item: &OutputTime,
+    options: &RenderOptions,
+    show_day: bool,
+    last: bool,
+) -> String {
+    let width = options.pretty_width;
+    // `timestamp` is the zone's wall-clock time read as UTC, so its date is the zone's date.
+    let time = match DateTime::from_timestamp(item.timestamp, 0) {
+        Some(t) if options.show_date => format!("{} {}", t.date_naive(), item.timestring),
+        _ => item.timestring.clone(),
+    };
+    let dst_marker = if item.is_dst { "(DST)" } else { "" };
+    let delta = if options.delta {
+        format!("({})", format_delta(item.delta))
+    } else {
+        "".to_string()
+    };
+    let day_offset = if show_day && !options.show_date {
+        item.day_offset_str.as_str()
+    } else {
+        ""
+    };
+    let note = match &item.note {
+        Some(t) => format!("- {}", t),
+        None => "".to_string(),
+    };
+    let suffix: Vec<&str> = [dst_marker, delta.as_str(), day_offset, note.as_str()]
+        .into_iter()
+        .filter(|t| !t.is_empty())
+        .collect();
+    let name = row_name(item, options);
+    // With `--highlight` every row gets a gutter, so the marked one doesn't push its columns over.
+    let gutter = match &options.highlight {
+        Some(t) if *t == item.timezone_name => "> ",
+        Some(_) => "  ",
+        None => "",
+    };
+    let head = format!("{0}{1: <2$} ", gutter, fit_to_width(&name, width), width);
+    let mut output = format!("{}{} {}\n", head, time, suffix.join(" "));
+    if let Some(wrap) = options.wrap {
+        // Each marker is kept whole, and so is the note unless it can't fit on a line of its own.
+        let mut units: Vec<String> = [time.as_str(), dst_marker, delta.as_str(), day_offset]
+            .into_iter()
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect();
+        if let Some(t) = &item.note {
+            let indent = head.chars().count().min(wrap / 2);
+            if indent + note.chars().count() <= wrap {
+                units.push(note.clone());
+            } else {
+                let mut words = t.split_whitespace();
+                units.push(format!("- {}", words.next().unwrap_or_default()));
+                units.extend(words.map(|w| w.to_string()));
+            }
+        }
+        output = format!("{}\n", wrap_row(&head, &units, wrap));
+    }
+    if item.separator && !last {
+        let mut length = gutter.len() + width + 1 + time.chars().count();
+        if let Some(wrap) = options.wrap {
+            length = length.min(wrap);
+        }
+        let line = match item.separator_style.as_deref() {
+            None | Some("dash") => "-".repeat(length),
+            Some("double") => "=".repeat(length),
+            Some("blank") => "".to_string(),
+            Some(label) => {
+                let label = format!("-- {} ", label);
+                let rest = length.saturating_sub(label.chars().count());
+                (label + &"-".repeat(rest)).chars().take(length).collect()
+            }
+        };
+        output += &format!("{}\n", line);
+    }
+    output
+}
+
+// The name a pretty row starts with, before it's fitted to the column.
+pub fn row_name(item: &OutputTime, options: &RenderOptions) -> String {
+    if options.truncate_names {
+        match &item.timezone_nickname {
+            Some(t) => format!("[{}] {}", t, city_name(&item.timezone_name)),
+            None => city_name(&item.timezone_name),
+        }
+    } else {
+        item.displayed_name.clone()
+    }
+}
+
+// Breaks a pretty row between `units` so each line fits in `wrap` characters, keeping `head` (the
+// name column) together. Later lines are indented to the time column, or half of `wrap` if that
+// is narrower. A unit is never split.
+pub fn wrap_row(head: &str, units: &[String], wrap: usize) -> String {
+    let indent = " ".repeat(head.chars().count().min(wrap / 2));
+    let mut lines: Vec<String> = Vec::new();
+    let mut line: String = head.to_string();
+    let mut fresh = true;
+    for unit in units {
+        let length = line.chars().count() + unit.chars().count() + if fresh { 0 } else { 1 };
+        // The name column can go on a line of its own, but every later line gets at least a unit.
+        if length > wrap && (!fresh || lines.is_empty()) {
+            lines.push(line.trim_end().to_string());
+            line = indent.clone();
+            fresh = true;
+        }
+        if !fresh {
+            line.push(' ');
+        }
+        line += unit;
+        fresh = false;
+    }
+    lines.push(line.trim_end().to_string());
+    lines.join("\n")
+}
+
+// `-3h`, `+5h45m`.
+pub fn format_delta(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let (hours, minutes) = (seconds.abs() / 3600, seconds.abs() / 60 % 60);
+    if minutes == 0 {
+        format!("{}{}h", sign, hours)
+    } else {
+        format!("{}{}h{:02}m", sign, hours, minutes)
+    }
+}
+
+// `in 3 hours`, `2 days ago`, in the largest unit that fits like Discord's relative style.
+pub fn relative_time(seconds: i64) -> String {
+    let units = [
+        (365 * 86400, "year"),
+        (30 * 86400, "month"),
+        (86400, "day"),
+        (3600, "hour"),
+        (60, "minute"),
+    ];
+    let amount = seconds.abs();
+    let phrase = match units.iter().find(|(size, _)| amount >= *size) {
+        Some((size, unit)) => {
+            let count = amount / size;
+            format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+        }
+        None => "a few seconds".to_string(),
+    };
+    if seconds < 0 {
+        format!("{} ago", phrase)
+    } else {
+        format!("in {}", phrase)
+    }
+}
+
+pub fn day_label(day_offset: i32) -> String {
+    match day_offset {
+        -1 => "Yesterday".to_string(),
+        0 => "Today".to_string(),
+        1 => "Tomorrow".to_string(),
+        _ => format!("{:+} days", day_offset),
+    }
+}
+
+// Truncates with an ellipsis so a long name can't push the time out of its column.
+pub fn fit_to_width(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut res: String = text.chars().take(width.saturating_sub(1)).collect();
+    res.push('…');
+    res
+}
+
+// Quotes a field per RFC 4180 when it holds a delimiter, quote or line break.
+pub fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+// tsv has no quoting, so tabs and line breaks are written as escapes instead.
+pub fn tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+// The `TZ` variable may carry a leading `:` (POSIX "implementation defined" form), which is
+// stripped before looking the name up. Anything chrono-tz can't parse falls back to system local.
+pub fn env_timezone() -> Option<Tz> {
+    let value = env::var("TZ").ok()?;
+    value.trim_start_matches(':').parse::<Tz>().ok()
+}
+
+// Accepts `+5:45`, `-03:30`, `+0545` and `+9`. The sign is required so a bare hour isn't mistaken
+// for an offset.
+pub fn parse_fixed_offset(input: &str) -> Option<FixedOffset> {
+    let input = input.trim();
+    let sign = match input.chars().next()? {
+        '+' => 1,
+        '-' => -1,
+        _ => return None,
+    };
+    let rest = &input[1..];
+    if rest.is_empty() || !rest.chars().all(|c| c.is_ascii_digit() || c == ':') {
+        return None;
+    }
+    let (hours, minutes) = match rest.split_once(':') {
+        Some(t) => t,
+        None if rest.len() > 2 => rest.split_at(rest.len() - 2),
+        None => (rest, "0"),
+    };
+    // Two digits each at most, so the seconds below can't overflow.
+    if hours.len() > 2 || minutes.len() > 2 {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if minutes >= 60 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+// Percent-encodes everything but unreserved characters and `/`, for zone names in a query string.
+pub fn url_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+pub fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+// Each line of `text` as a monospaced `<text>` element, sized to fit.
+pub fn svg_document(text: &str) -> String {
+    let lines: Vec<&str> = text.trim_end_matches('\n').lines().collect();
+    let columns = lines.iter().map(|t| t.chars().count()).max().unwrap_or(0);
+    let (char_width, line_height, padding) = (8.4, 20, 10);
+    let width = (columns as f64 * char_width).ceil() as usize + padding * 2;
+    let height = lines.len() * line_height + padding * 2;
+
+    let mut output = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n",
+        width, height
+    );
+    output += "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>\n";
+    output +=
+        "<g font-family=\"monospace\" font-size=\"14\" fill=\"black\" xml:space=\"preserve\">\n";
+    for (i, line) in lines.iter().enumerate() {
+        output += &format!(
+            "<text x=\"{}\" y=\"{}\">{}</text>\n",
+            padding,
+            padding + (i + 1) * line_height - 5,
+            html_escape(line)
+        );
+    }
+    output += "</g>\n</svg>\n";
+    output
+}
+
+// `America/Argentina/Buenos_Aires` -> `Buenos Aires`.
+pub fn city_name(tz_name: &str) -> String {
+    let city = match tz_name.rsplit_once('/') {
+        Some((_, t)) => t,
+        None => tz_name,
+    };
+    city.replace('_', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_offset_forms() {
+        let east = |t: &str| parse_fixed_offset(t).map(|o| o.local_minus_utc());
+        assert_eq!(east("+5:45"), Some(5 * 3600 + 45 * 60));
+        assert_eq!(east("+0545"), Some(5 * 3600 + 45 * 60));
+        assert_eq!(east("-03:30"), Some(-(3 * 3600 + 30 * 60)));
+        assert_eq!(east("+9"), Some(9 * 3600));
+        assert_eq!(east("9"), None);
+        assert_eq!(east("+5:60"), None);
+        assert_eq!(east("+24:00"), None);
+        assert_eq!(east("+999999:00"), None);
+        assert_eq!(east("+12345"), None);
+    }
+
+    #[test]
+    fn pasted_whitespace_is_tolerated() {
+        let expected = NaiveTime::from_hms_opt(15, 30, 0);
+        assert_eq!(parse_time_of_day(" 15:30 ").unwrap(), expected);
+        assert_eq!(parse_time_of_day("15 : 30").unwrap(), expected);
+        assert_eq!(parse_time_of_day("15:30\n").unwrap(), expected);
+        assert!(parse_time_of_day("1 5:30").is_err());
+        assert!(parse_time_of_day("15:3x").is_err());
+    }
+
+    #[test]
+    fn end_of_day_is_next_midnight() {
+        assert!(is_end_of_day("24:00"));
+        assert!(is_end_of_day("24:00:00"));
+        assert!(is_end_of_day(" 24 : 00 "));
+        assert!(!is_end_of_day("24:01"));
+        assert!(!is_end_of_day("00:00"));
+        assert_eq!(
+            parse_time_of_day("24:00").unwrap(),
+            NaiveTime::from_hms_opt(0, 0, 0)
+        );
+        let utc = FixedOffset::east_opt(0);
+        let at = |t: &str| {
+            get_comparison_date_time(Some(t.to_string()), None, utc, None)
+                .unwrap()
+                .as_fixed()
+        };
+        assert_eq!(at("24:00") - at("00:00"), Duration::days(1));
+    }
+
+    #[test]
+    fn twelve_hour_boundaries() {
+        let at = |h, m| NaiveTime::from_hms_opt(h, m, 0);
+        assert_eq!(parse_time_of_day("12am").unwrap(), at(0, 0));
+        assert_eq!(parse_time_of_day("12:30am").unwrap(), at(0, 30));
+        assert_eq!(parse_time_of_day("1am").unwrap(), at(1, 0));
+        assert_eq!(parse_time_of_day("11am").unwrap(), at(11, 0));
+        assert_eq!(parse_time_of_day("11 am").unwrap(), at(11, 0));
+        assert_eq!(parse_time_of_day("12pm").unwrap(), at(12, 0));
+        assert_eq!(parse_time_of_day("12:59pm").unwrap(), at(12, 59));
+        assert_eq!(parse_time_of_day("3PM").unwrap(), at(15, 0));
+        assert_eq!(parse_time_of_day("11:59pm").unwrap(), at(23, 59));
+        assert!(parse_time_of_day("0am").is_err());
+        assert!(parse_time_of_day("13pm").is_err());
+        assert!(parse_time_of_day("15pm").is_err());
+    }
+
+    #[test]
+    fn csv_fields_are_quoted() {
+        assert_eq!(csv_field("Asia/Tokyo"), "Asia/Tokyo");
+        assert_eq!(csv_field("East, Coast"), "\"East, Coast\"");
+        assert_eq!(csv_field("the \"big\" one"), "\"the \"\"big\"\" one\"");
+        assert_eq!(csv_field("two\nlines"), "\"two\nlines\"");
+    }
 }
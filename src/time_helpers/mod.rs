@@ -1,15 +1,174 @@
-use chrono::{DateTime, Datelike};
-use chrono_tz::{Tz, TZ_VARIANTS};
-use std::str::FromStr;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, NaiveDate, Offset, TimeZone, Timelike, Utc,
+};
+use chrono_tz::{OffsetComponents, OffsetName, Tz, TZ_VARIANTS};
+use std::collections::HashMap;
 
 use crate::config;
 mod helpers;
 
-pub fn render_time(
-    timezone_input: Option<String>,
-    time: Option<String>,
-    output_format: Option<String>,
-) -> Option<String> {
+pub use helpers::{csv_field, Prefer, MIN_JSON_COLUMNS};
+
+// How an input turned into a zone, see `resolve_zone`.
+pub enum ResolveResult {
+    Exact(Tz),
+    Ambiguous(Vec<Tz>),
+    NotFound,
+}
+
+impl ResolveResult {
+    // The zone, or `None` after saying why there isn't one.
+    pub fn zone(self, input: &str, not_found: &str) -> Option<Tz> {
+        match self {
+            ResolveResult::Exact(tz) => Some(tz),
+            ResolveResult::Ambiguous(zones) => {
+                eprintln!("{} matches more than one timezone! It could mean:", input);
+                for tz in zones {
+                    eprintln!("  {}", tz.name());
+                }
+                None
+            }
+            ResolveResult::NotFound => {
+                eprintln!("{}", not_found);
+                None
+            }
+        }
+    }
+}
+
+// Every command reads a timezone input through here: a saved nickname, then the saved
+// timezones, then every known timezone, so the same input means the same zone everywhere.
+pub fn resolve_zone(input: &str, config: &config::SavedDefines) -> ResolveResult {
+    resolve_zone_ranked(input, config).0
+}
+
+// `resolve_zone` along with how closely it matched, as `helpers::match_rank` counts it. Exact names
+// and nicknames are 0.
+pub fn resolve_zone_ranked(input: &str, config: &config::SavedDefines) -> (ResolveResult, u8) {
+    let saved = || {
+        config
+            .timezones
+            .iter()
+            .filter_map(|t| t.timezone_name.parse::<Tz>().ok())
+    };
+    if config::is_strict() {
+        let exact = config
+            .timezones
+            .iter()
+            .find(|t| t.timezone_name == input || t.nickname.as_deref() == Some(input))
+            .and_then(|t| t.timezone_name.parse::<Tz>().ok())
+            .or_else(|| input.parse::<Tz>().ok());
+        return match exact {
+            Some(tz) => (ResolveResult::Exact(tz), 0),
+            None => (ResolveResult::NotFound, u8::MAX),
+        };
+    }
+
+    // The whole nickname has to match, so a short word can't pick out part of a longer one.
+    let nick_term = config::normalize_nickname(input).to_lowercase();
+    let nicknamed = config.timezones.iter().find(|t| {
+        t.nickname
+            .as_deref()
+            .is_some_and(|nick| config::normalize_nickname(nick).to_lowercase() == nick_term)
+    });
+    if let Some(t) = nicknamed {
+        config::verbose!("Nickname {:?} matched {}", t.nickname, t.timezone_name);
+        if let Ok(tz) = t.timezone_name.parse::<Tz>() {
+            return (ResolveResult::Exact(tz), 0);
+        }
+    }
+
+    match best_matches(saved(), input) {
+        (ResolveResult::NotFound, _) => {
+            config::verbose!("No saved timezone matched {}", input);
+            best_matches(TZ_VARIANTS.iter().copied(), input)
+        }
+        t => t,
+    }
+}
+
+// The closest of `zones` to `input`. Ties go to the first alphabetically, except between loose
+// substring matches, where picking one would be a guess.
+fn best_matches(zones: impl Iterator<Item = Tz>, input: &str) -> (ResolveResult, u8) {
+    let mut candidates: Vec<(u8, Tz)> = zones
+        .filter_map(|tz| helpers::match_rank(tz.name(), input).map(|rank| (rank, tz)))
+        .collect();
+    candidates.sort_by(|a, b| (a.0, a.1.name()).cmp(&(b.0, b.1.name())));
+    let best_rank = match candidates.first() {
+        Some((rank, _)) => *rank,
+        None => return (ResolveResult::NotFound, u8::MAX),
+    };
+    let tied: Vec<Tz> = candidates
+        .iter()
+        .take_while(|(rank, _)| *rank == best_rank)
+        .map(|(_, tz)| *tz)
+        .collect();
+    config::verbose!(
+        "Timezone {} matched {} (rank {}, {} tied)",
+        tied[0].name(),
+        input,
+        best_rank,
+        tied.len()
+    );
+    if best_rank >= 4 && tied.len() > 1 {
+        (ResolveResult::Ambiguous(tied), best_rank)
+    } else {
+        (ResolveResult::Exact(tied[0]), best_rank)
+    }
+}
+
+pub fn dst_transitions(tz_input: String) -> Option<String> {
+    let config = match config::load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+
+    let tz = resolve_zone(&tz_input, &config).zone(&tz_input, "Timezone not found!")?;
+
+    let year = Utc::now().with_timezone(&tz).year();
+    let start = Utc.with_ymd_and_hms(year, 1, 1, 0, 0, 0).unwrap();
+    let end = Utc.with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0).unwrap();
+    let offset_at = |instant: DateTime<Utc>| instant.with_timezone(&tz).offset().fix();
+
+    let mut output = format!("DST transitions for {} in {}\n\n", tz.name(), year);
+    let mut found = false;
+    let mut prev = start;
+    // Step an hour at a time, then narrow down to the second once the offset is seen to change.
+    while prev < end {
+        let next = prev + Duration::hours(1);
+        if offset_at(prev) != offset_at(next) {
+            let (mut low, mut high) = (prev, next);
+            while high - low > Duration::seconds(1) {
+                let mid = low + (high - low) / 2;
+                if offset_at(mid) == offset_at(low) {
+                    low = mid;
+                } else {
+                    high = mid;
+                }
+            }
+            let before = low.with_timezone(&tz);
+            let after = high.with_timezone(&tz);
+            output += &format!(
+                "{0: <25} {1} ({2}) -> {3} ({4})\n",
+                high.format("%Y-%m-%d %H:%M:%S UTC"),
+                before.offset().abbreviation(),
+                before.offset().fix(),
+                after.offset().abbreviation(),
+                after.offset().fix()
+            );
+            found = true;
+        }
+        prev = next;
+    }
+    if !found {
+        output += "No transitions this year.\n";
+    }
+    Some(output)
+}
+
+pub fn describe_timezone(tz_input: String) -> Option<String> {
     let config = match config::load_config() {
         Ok(t) => t,
         Err(_e) => {
@@ -17,193 +176,1623 @@ pub fn render_time(
         }
     };
 
+    let saved = &config.timezones[config::saved_timezone_index(&config, &tz_input, u8::MAX)?];
+    let tz = match saved.timezone_name.parse::<Tz>() {
+        Ok(t) => t,
+        Err(_e) => {
+            eprintln!("Timezone not found!");
+            return None;
+        }
+    };
+
+    let now = Utc::now().with_timezone(&tz);
+    let offset = now.offset();
+    let nick = match &saved.nickname {
+        Some(t) => t.to_string(),
+        None => "".to_string(),
+    };
+
     let mut output: String = "".to_string();
+    output += &format!("{0: <25} {1}\n", "Timezone", tz.name());
+    output += &format!("{0: <25} {1}\n", "Nickname", nick);
+    output += &format!("{0: <25} {1}\n", "Separator", saved.separator);
+    output += &format!(
+        "{0: <25} {1}\n",
+        "Note",
+        saved.note.as_deref().unwrap_or("")
+    );
+    output += &format!("{0: <25} {1}\n", "Current Offset", offset.fix());
+    output += &format!("{0: <25} {1}\n", "Abbreviation", offset.abbreviation());
+    output += &format!("{0: <25} {1}", "DST Active", !offset.dst_offset().is_zero());
+    Some(output)
+}
+
+#[derive(Clone)]
+pub struct RenderOptions {
+    pub timezone_input: Option<String>,
+    pub time: Option<String>,
+    pub output_format: Option<String>,
+    pub pretty_width: usize,
+    pub oneline_separator: String,
+    pub reverse: bool,
+    // Replaces the pretty header; `{zone}`, `{time}` and `{date}` are filled in.
+    pub header_format: Option<String>,
+    // Limits csv, tsv and json output to these `OutputTime` fields.
+    pub columns: Option<Vec<String>>,
+    // Show milliseconds instead of truncating to whole seconds.
+    pub subsec: bool,
+    // `day` splits pretty output into a section per day offset.
+    pub group_by: Option<String>,
+    // Print each zone's date in pretty output in place of the day offset.
+    pub show_date: bool,
+    // Show how far each zone is ahead of or behind the reference in pretty output.
+    pub delta: bool,
+    // Zone to measure day offsets and deltas from, instead of the reference.
+    pub anchor: Option<String>,
+    // `time` (the default) or `from-pivot`.
+    pub sort: Option<String>,
+    // Show only the city part of each name in pretty output.
+    pub truncate_names: bool,
+    // How the header names a saved reference zone: `name` (the default), `full` or `nick`.
+    pub header_zone_name: Option<String>,
+    // Row for `template` output, with `{field}` for any `OutputTime` field.
+    pub template: Option<String>,
+    // Printed before and after the rows of `template` output.
+    pub template_header: Option<String>,
+    pub template_footer: Option<String>,
+    // Wrap pretty rows to this many columns.
+    pub wrap: Option<usize>,
+    // Leave out zones on the same date as the anchor.
+    pub cross_day_only: bool,
+    // Leave out zones on a different date to the anchor.
+    pub same_day_only: bool,
+    // Saved zone whose pretty row gets a `>` in front.
+    pub highlight: Option<String>,
+    // Read a lone number like `1700000000` as a timestamp instead of an hour.
+    pub bare_epoch: bool,
+    // Size the pretty name column to its longest name, kept within these.
+    pub min_width: Option<usize>,
+    pub max_width: Option<usize>,
+}
+
+impl ::std::default::Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            timezone_input: None,
+            time: None,
+            output_format: None,
+            pretty_width: 25,
+            oneline_separator: " | ".to_string(),
+            reverse: false,
+            header_format: None,
+            columns: None,
+            subsec: false,
+            group_by: None,
+            show_date: false,
+            delta: false,
+            anchor: None,
+            sort: None,
+            truncate_names: false,
+            header_zone_name: None,
+            template: None,
+            template_header: None,
+            template_footer: None,
+            wrap: None,
+            cross_day_only: false,
+            same_day_only: false,
+            highlight: None,
+            bare_epoch: false,
+            min_width: None,
+            max_width: None,
+        }
+    }
+}
 
-    let output_fmt: String = match output_format {
+// Zones whose city segment matches `city`, best match first, for picking one to `tc d add`.
+pub fn suggest_timezones(city: String) -> Option<String> {
+    let mut candidates: Vec<(u8, Tz)> = TZ_VARIANTS
+        .iter()
+        .filter_map(|tz| {
+            let city_name = match tz.name().rsplit_once('/') {
+                Some((_, t)) => t,
+                None => tz.name(),
+            };
+            helpers::match_rank(city_name, &city).map(|rank| (rank, *tz))
+        })
+        .collect();
+    if candidates.is_empty() {
+        eprintln!("No timezones found for that city!");
+        return None;
+    }
+    candidates.sort_by(|a, b| (a.0, a.1.name()).cmp(&(b.0, b.1.name())));
+
+    let now = Utc::now();
+    let lines: Vec<String> = candidates
+        .iter()
+        .map(|(_, tz)| {
+            format!(
+                "{0: <25} UTC{1}",
+                tz.name(),
+                now.with_timezone(tz).offset().fix()
+            )
+        })
+        .collect();
+    Some(lines.join("\n"))
+}
+
+// How far `to` is ahead of `from` right now, as `+13:00` or `-5:30`.
+pub fn zone_gap(from: String, to: String) -> Option<String> {
+    let config = match config::load_config() {
+        Ok(t) => t,
+        Err(_e) => {
+            return None;
+        }
+    };
+    let now = Utc::now();
+    let offset_of = |input: &str| -> Option<i32> {
+        if let Some(t) = helpers::parse_fixed_offset(input) {
+            return Some(t.local_minus_utc());
+        }
+        resolve_zone(input, &config)
+            .zone(input, "Timezone not found!")
+            .map(|tz| now.with_timezone(&tz).offset().fix().local_minus_utc())
+    };
+    let (from_offset, to_offset) = match (offset_of(&from), offset_of(&to)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return None,
+    };
+
+    let gap = to_offset - from_offset;
+    let sign = if gap < 0 { '-' } else { '+' };
+    Some(format!(
+        "{}{}:{:02}",
+        sign,
+        gap.abs() / 3600,
+        gap.abs() / 60 % 60
+    ))
+}
+
+// Every known zone currently at `offset`, with the abbreviation it is using.
+pub fn zones_at_offset(offset: String) -> Option<String> {
+    let offset = match helpers::parse_fixed_offset(&offset) {
         Some(t) => t,
-        None => "pretty".to_string(),
+        None => {
+            eprintln!("Offset not understood! Use a form like +09:00 or -5.");
+            return None;
+        }
     };
+    let now = Utc::now();
+    let lines: Vec<String> = TZ_VARIANTS
+        .iter()
+        .map(|tz| now.with_timezone(tz))
+        .filter(|t| t.offset().fix() == offset)
+        .map(|t| {
+            format!(
+                "{0: <25} {1}",
+                t.timezone().name(),
+                t.offset().abbreviation()
+            )
+        })
+        .collect();
+    if lines.is_empty() {
+        eprintln!("No timezones are at UTC{} right now!", offset);
+        return None;
+    }
+    Some(lines.join("\n"))
+}
+
+// Used by `url` output unless the config sets `url_base`.
+const DEFAULT_URL_BASE: &str = "https://www.timeanddate.com/worldclock/fixedtime.html";
+
+// tzdata's zone.tab, from the same release chrono-tz was built from. Each line is a country code,
+// coordinates, a zone and sometimes a comment on which part of the country it covers.
+const ZONE_TAB: &str = include_str!("zone.tab");
+
+pub fn zones_in_country(country: String) -> Option<String> {
+    let code = country.trim().to_uppercase();
+    let lines: Vec<String> = ZONE_TAB
+        .lines()
+        .filter(|t| !t.starts_with('#'))
+        .map(|t| t.split('\t').collect::<Vec<&str>>())
+        .filter(|fields| fields.len() >= 3 && fields[0] == code)
+        .map(|fields| format!("{0: <25} {1}", fields[2], fields.get(3).unwrap_or(&"")))
+        .map(|t| t.trim_end().to_string())
+        .collect();
+    if lines.is_empty() {
+        eprintln!("No timezones found for country code {}!", code);
+        return None;
+    }
+    Some(lines.join("\n"))
+}
+
+// The config with its saved timezones already resolved to `Tz`, so repeated renders (the curses
+// loop) neither reload the file nor search every known timezone again.
+pub struct RenderContext {
+    config: config::SavedDefines,
+    // The rows to show, the saved list unless `--zones` gave others.
+    listed: Vec<config::SavedTimezones>,
+    zones: Vec<(usize, Tz)>,
+}
+
+impl RenderContext {
+    pub fn load() -> Option<Self> {
+        match config::load_config() {
+            Ok(t) => Some(Self::new(t)),
+            Err(_e) => None,
+        }
+    }
+
+    pub fn new(config: config::SavedDefines) -> Self {
+        let listed = config.timezones.clone();
+        Self::listing(config, listed)
+    }
+
+    // Shows `inputs` instead of the saved list. The config is still used to read `-t`.
+    pub fn with_zones(self, inputs: &str) -> Option<Self> {
+        let mut listed: Vec<config::SavedTimezones> = Vec::new();
+        for input in inputs
+            .split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+        {
+            let not_found = format!("Timezone {} not found, leaving it out!", input);
+            let tz = match resolve_zone(input, &self.config).zone(input, &not_found) {
+                Some(t) => t,
+                None => continue,
+            };
+            if listed.iter().any(|t| t.timezone_name == tz.name()) {
+                continue;
+            }
+            listed.push(config::SavedTimezones {
+                timezone_name: tz.name().to_string(),
+                nickname: None,
+                separator: false,
+                pinned: false,
+                note: None,
+                separator_style: None,
+                last_used: None,
+                extra: HashMap::new(),
+            });
+        }
+        if listed.is_empty() {
+            eprintln!("None of the timezones given to --zones were found!");
+            return None;
+        }
+        Some(Self::listing(self.config, listed))
+    }
 
-    let mut timezone: Option<Tz> = None;
+    fn listing(config: config::SavedDefines, listed: Vec<config::SavedTimezones>) -> Self {
+        let mut zones: Vec<(usize, Tz)> = listed
+            .iter()
+            .enumerate()
+            .filter_map(|(i, t)| t.timezone_name.parse::<Tz>().ok().map(|tz| (i, tz)))
+            .collect();
+        // Alphabetical, matching `TZ_VARIANTS`, so zones at the same time keep a stable order.
+        zones.sort_by(|a, b| a.1.name().cmp(b.1.name()));
+        Self {
+            config,
+            listed,
+            zones,
+        }
+    }
+}
 
-    if timezone_input.is_some() {
-        let mut search_term = timezone_input.unwrap();
+// Everything a render works out before any formatting happens.
+struct TimeList {
+    header: String,
+    header_zone: String,
+    header_time: String,
+    header_date: NaiveDate,
+    epoch: i64,
+    url_base: Option<String>,
+    times: Vec<helpers::OutputTime>,
+}
 
-        for tz in config.timezones.clone() {
-            match tz.nickname {
-                Some(nick) => {
-                    if nick
-                        .to_lowercase()
-                        .contains(search_term.to_lowercase().as_str())
-                    {
-                        search_term = tz.timezone_name.clone();
-                        break;
+// The offsets an abbreviation such as `JST` or `PST` is used for, each with a few of the zones using
+// it. Zones are checked in mid January and mid July of this year, so abbreviations only used in
+// summer or only in winter are found whatever the date. Only all-capitals input counts, and never
+// the name of an actual zone like `EST` or `UTC`.
+fn abbreviation_offsets(input: &str) -> Vec<(FixedOffset, Vec<&'static str>)> {
+    let input = input.trim();
+    if !(2..=5).contains(&input.len())
+        || !input.chars().all(|c| c.is_ascii_uppercase())
+        || input.parse::<Tz>().is_ok()
+    {
+        return Vec::new();
+    }
+    let year = Utc::now().year();
+    let mut offsets: Vec<(FixedOffset, Vec<&'static str>)> = Vec::new();
+    for month in [1, 7] {
+        let instant = Utc.with_ymd_and_hms(year, month, 15, 12, 0, 0).unwrap();
+        for tz in TZ_VARIANTS.iter() {
+            let offset = *instant.with_timezone(tz).offset();
+            if offset.abbreviation() != input {
+                continue;
+            }
+            match offsets.iter_mut().find(|(t, _)| *t == offset.fix()) {
+                Some((_, zones)) => {
+                    if !zones.contains(&tz.name()) {
+                        zones.push(tz.name());
                     }
                 }
-                None => continue,
+                None => offsets.push((offset.fix(), [tz.name()].to_vec())),
             }
         }
+    }
+    for (_, zones) in offsets.iter_mut() {
+        zones.truncate(3);
+    }
+    offsets.sort_by_key(|(t, _)| t.local_minus_utc());
+    offsets
+}
+
+// Splits `noon tokyo` into a time and the zone to read it in. The zone is tried longest first; if
+// no split gives both a valid time and a zone named outright, the whole string stays a time. A
+// loose substring match doesn't count, or `11 am` would be read in whatever zone contains `am`.
+fn split_time_and_zone(config: &config::SavedDefines, input: &str) -> Option<(String, String)> {
+    let tokens: Vec<&str> = input.split_whitespace().collect();
+    for i in 1..tokens.len() {
+        let time = tokens[..i].join(" ");
+        let zone = tokens[i..].join(" ");
+        if helpers::parse_time_of_day(&time).is_err() {
+            continue;
+        }
+        if matches!(zone.to_lowercase().as_str(), "am" | "pm") {
+            continue;
+        }
+        let named = match resolve_zone_ranked(&zone, config) {
+            (ResolveResult::Exact(_), rank) => rank <= 3,
+            _ => false,
+        };
+        if helpers::parse_fixed_offset(&zone).is_some()
+            || !abbreviation_offsets(&zone).is_empty()
+            || named
+        {
+            config::verbose!("Split {} into time {} and timezone {}", input, time, zone);
+            return Some((time, zone));
+        }
+    }
+    None
+}
+
+// `-t tokyo,east` as the zones to read the times in one after another, or `None` when it names a
+// single zone. The whole input is tried first so a nickname like `East, Coast` still works.
+fn split_pivots(config: &config::SavedDefines, input: &str) -> Option<Vec<String>> {
+    if !input.contains(',') || matches!(resolve_zone(input, config), ResolveResult::Exact(_)) {
+        return None;
+    }
+    Some(
+        input
+            .split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| t.to_string())
+            .collect(),
+    )
+}
+
+// The saved zones `render` would read its times in, so `tc t` can mark them as used.
+pub fn saved_pivots(ctx: &RenderContext, options: &RenderOptions, times: &[String]) -> Vec<String> {
+    let inputs: Vec<String> = match &options.timezone_input {
+        Some(t) => split_pivots(&ctx.config, t).unwrap_or_else(|| vec![t.clone()]),
+        None => times
+            .iter()
+            .chain(options.time.iter())
+            .filter_map(|t| split_time_and_zone(&ctx.config, t).map(|(_, zone)| zone))
+            .collect(),
+    };
+    let mut names: Vec<String> = inputs
+        .iter()
+        .filter(|t| helpers::parse_fixed_offset(t).is_none() && abbreviation_offsets(t).is_empty())
+        .filter_map(|t| match resolve_zone(t, &ctx.config) {
+            ResolveResult::Exact(tz) => Some(tz),
+            _ => None,
+        })
+        .map(|tz| tz.name().to_string())
+        .collect();
+    names.dedup();
+    names
+}
+
+// Where the typed time is read and what day offsets count from, worked out once so many instants
+// can be listed against it.
+struct Reference {
+    time_input: Option<String>,
+    timezone: Option<Tz>,
+    fixed_offset: Option<FixedOffset>,
+    header_zone: String,
+    anchor: Option<(Option<Tz>, Option<FixedOffset>)>,
+}
+
+impl Reference {
+    // The instant as wall-clock time in the reference.
+    fn at(&self, instant: DateTime<Utc>) -> DateTime<FixedOffset> {
+        match (self.fixed_offset, self.timezone) {
+            (Some(f), _) => instant.with_timezone(&f),
+            (None, Some(t)) => instant.with_timezone(&t).fixed_offset(),
+            (None, None) => instant.with_timezone(&chrono::Local).fixed_offset(),
+        }
+    }
+}
 
-        for tz in TZ_VARIANTS {
-            let tz_name = tz.name().to_string();
-            if config::saved_list_contains_timezone(&config, &tz_name).1 {
-                if tz_name
-                    .to_lowercase()
-                    .contains(search_term.to_lowercase().as_str())
-                {
-                    timezone = Some(tz);
+fn resolve_reference(config: &config::SavedDefines, options: &RenderOptions) -> Option<Reference> {
+    // A zone inside the time argument only counts when `--timezone` wasn't given.
+    let (time_input, timezone_input) = match (&options.time, &options.timezone_input) {
+        (Some(t), None) => match split_time_and_zone(config, t) {
+            Some((time, zone)) => (Some(time), Some(zone)),
+            None => (options.time.clone(), None),
+        },
+        _ => (options.time.clone(), options.timezone_input.clone()),
+    };
+    let mut fixed_offset: Option<FixedOffset> = match &timezone_input {
+        Some(t) => helpers::parse_fixed_offset(t),
+        None => None,
+    };
+    // An abbreviation like `JST` means the offset it stands for rather than any one zone, and
+    // comes before name matching so `IST` can't end up as Europe/Istanbul.
+    let mut abbreviation: Option<String> = None;
+    if let (Some(t), None) = (&timezone_input, fixed_offset) {
+        let offsets = abbreviation_offsets(t);
+        match offsets.len() {
+            0 => {}
+            1 => {
+                config::verbose!("Abbreviation {} is UTC{}", t, offsets[0].0);
+                fixed_offset = Some(offsets[0].0);
+                abbreviation = Some(t.to_string());
+            }
+            _ => {
+                eprintln!("{} is ambiguous! It could mean:", t);
+                for (offset, zones) in offsets {
+                    eprintln!("  UTC{} ({})", offset, zones.join(", "));
                 }
+                return None;
             }
         }
     }
+    let input_timezone: Option<Tz> = match (&timezone_input, fixed_offset) {
+        // Nothing found falls back to the local zone below, but a guess between several doesn't.
+        (Some(t), None) => match resolve_zone(t, config) {
+            ResolveResult::NotFound => None,
+            found => Some(found.zone(t, "")?),
+        },
+        _ => None,
+    };
+    if config::is_strict()
+        && timezone_input.is_some()
+        && fixed_offset.is_none()
+        && input_timezone.is_none()
+    {
+        eprintln!("No timezone or nickname exactly matches the input!");
+        return None;
+    }
+    let timezone = match fixed_offset {
+        Some(_) => None,
+        None => input_timezone.or_else(helpers::env_timezone),
+    };
+    match (fixed_offset, timezone) {
+        (Some(t), _) => config::verbose!("Reference is the fixed offset {}", t),
+        (None, Some(t)) => config::verbose!("Reference timezone is {}", t.name()),
+        (None, None) => config::verbose!("Reference is the system local timezone"),
+    }
+
+    let header_zone = match (fixed_offset, input_timezone) {
+        (Some(f), _) => match abbreviation {
+            Some(t) => format!("{} (UTC{})", t, f),
+            None => format!("UTC{}", f),
+        },
+        (None, Some(t)) => {
+            let nickname = config
+                .timezones
+                .iter()
+                .find(|saved| saved.timezone_name == t.name())
+                .and_then(|saved| saved.nickname.clone());
+            // `full` names it the way its row does; `nick` falls back to the name without one.
+            match (options.header_zone_name.as_deref(), nickname) {
+                (Some("full"), Some(nick)) => format!("[{}] {}", nick, t.name()),
+                (Some("nick"), Some(nick)) => format!("[{}]", nick),
+                _ => t.name().to_string(),
+            }
+        }
+        (None, None) => "Local".to_string(),
+    };
+
+    // Day offsets and deltas are measured from the anchor, which is the reference unless
+    // `--anchor` names another zone.
+    let anchor = match &options.anchor {
+        Some(t) => match helpers::parse_fixed_offset(t) {
+            Some(f) => Some((None, Some(f))),
+            None => Some((
+                Some(resolve_zone(t, config).zone(t, "Anchor timezone not found!")?),
+                None,
+            )),
+        },
+        None => None,
+    };
+
+    Some(Reference {
+        time_input,
+        timezone,
+        fixed_offset,
+        header_zone,
+        anchor,
+    })
+}
 
-    let offset_comparison_datetime = match helpers::get_comparison_date_time(time, timezone) {
+fn collect_times(ctx: &RenderContext, options: &RenderOptions) -> Option<TimeList> {
+    let reference = resolve_reference(&ctx.config, options)?;
+    // A timestamp is already an instant, so it only needs putting in the reference.
+    if let Some(epoch) = reference
+        .time_input
+        .as_deref()
+        .and_then(|t| helpers::input_epoch(t, options.bare_epoch))
+    {
+        let instant = match DateTime::from_timestamp(epoch, 0) {
+            Some(t) => t,
+            None => {
+                eprintln!("Timestamp {} is out of range!", epoch);
+                return None;
+            }
+        };
+        return Some(list_times(ctx, options, &reference, reference.at(instant)));
+    }
+    // Now is already an instant too, so it isn't rebuilt on the local date, see `timestamp_for`.
+    if matches!(
+        reference
+            .time_input
+            .as_deref()
+            .map(helpers::parse_time_of_day),
+        None | Some(Ok(None))
+    ) {
+        return Some(list_times(
+            ctx,
+            options,
+            &reference,
+            reference.at(Utc::now()),
+        ));
+    }
+    let offset_comparison_datetime = match helpers::get_comparison_date_time(
+        reference.time_input.clone(),
+        reference.timezone,
+        reference.fixed_offset,
+        None,
+    ) {
         Ok(t) => t,
-        Err(_e) => {
-            eprintln!("Something went wrong when parsing the time!");
+        Err(e) => {
+            eprintln!("{}", e);
             return None;
         }
     };
+    Some(list_times(
+        ctx,
+        options,
+        &reference,
+        offset_comparison_datetime.as_fixed(),
+    ))
+}
 
-    if offset_comparison_datetime.kind == helpers::CurTimeKind::Tz {
-        let time = offset_comparison_datetime.tz_time.unwrap();
-        let fmt_string = "Time for ".to_string() + time.timezone().name();
-        if output_fmt == "pretty" {
-            output += &format!("{0: <25} {1}\n\n", fmt_string, time.time());
-        }
+// Every saved zone at `header_datetime`, sorted for display.
+fn list_times(
+    ctx: &RenderContext,
+    options: &RenderOptions,
+    reference: &Reference,
+    header_datetime: DateTime<FixedOffset>,
+) -> TimeList {
+    let config = &ctx.config;
+    let header_zone = reference.header_zone.clone();
+    let fmt_string = if header_zone == "Local" {
+        "Local Time".to_string()
     } else {
-        let time = offset_comparison_datetime.local_time.unwrap();
-        let fmt_string = "Local Time".to_string();
-        if output_fmt == "pretty" {
-            output += &format!("{0: <25} {1}\n\n", fmt_string, time.time());
+        format!("Time for {}", header_zone)
+    };
+    let anchor_time: DateTime<FixedOffset> = match reference.anchor {
+        Some((_, Some(f))) => header_datetime.with_timezone(&f),
+        Some((Some(tz), None)) => {
+            let offset = header_datetime.with_timezone(&tz).offset().fix();
+            header_datetime.with_timezone(&offset)
         }
-    }
+        _ => header_datetime,
+    };
+    // A time typed to the minute shows every zone to the minute, even one whose offset isn't a
+    // whole number of minutes, like the local mean time offsets in old tzdata.
+    let whole_minutes = reference.time_input.is_some()
+        && header_datetime.second() == 0
+        && header_datetime.nanosecond() == 0;
 
     let mut tz_list: Vec<helpers::OutputTime> = [].to_vec();
 
-    for tz in TZ_VARIANTS {
-        let tz_name = String::from_str(tz.name()).unwrap();
-        let contains = config::saved_list_contains_timezone(&config, &tz_name);
-        if contains.1 {
-            let converted_time: DateTime<Tz>;
-            if offset_comparison_datetime.kind == helpers::CurTimeKind::Tz {
-                let time = offset_comparison_datetime.tz_time.unwrap();
-                converted_time = time.with_timezone(&tz);
-            } else {
-                let time = offset_comparison_datetime.local_time.unwrap();
-                converted_time = time.with_timezone(&tz);
-            }
-
-            let mut offset_string: String;
-            let mut day_diff: u32 = 0;
-            if offset_comparison_datetime.kind == helpers::CurTimeKind::Tz {
-                let offset_time = offset_comparison_datetime.tz_time.unwrap();
-
-                if converted_time.day() != offset_time.day() {
-                    let converted_ts = helpers::convert_date_to_timestamp(
-                        converted_time.year(),
-                        converted_time.ordinal0(),
-                    );
-                    let local_ts = helpers::convert_date_to_timestamp(
-                        offset_time.year(),
-                        offset_time.ordinal0(),
-                    );
-                    if converted_ts > local_ts {
-                        day_diff = converted_ts - local_ts;
-                        offset_string = format!("(+{}", day_diff);
-                    } else {
-                        day_diff = local_ts - converted_ts;
-                        offset_string = format!("(-{}", day_diff);
-                    }
-                    if day_diff == 1 {
-                        offset_string += " day)";
-                    } else {
-                        offset_string += " days)";
-                    }
+    for (i, tz) in &ctx.zones {
+        let saved = &ctx.listed[*i];
+        let tz_name = tz.name().to_string();
+        let converted_time: DateTime<Tz> = header_datetime.with_timezone(tz);
+        let reference_date = anchor_time.date_naive();
+        let day_diff = (converted_time.date_naive() - reference_date).num_days() as i32;
+        let epoch_date = DateTime::UNIX_EPOCH.date_naive();
+        config::verbose!(
+            "{} is on {} (day {}), anchor is on {} (day {}), offset {:+}",
+            tz_name,
+            converted_time.date_naive(),
+            (converted_time.date_naive() - epoch_date).num_days(),
+            reference_date,
+            (reference_date - epoch_date).num_days(),
+            day_diff
+        );
+        let offset_string = match day_diff {
+            0 => "".to_string(),
+            1 | -1 => format!("({:+} day)", day_diff),
+            _ => format!("({:+} days)", day_diff),
+        };
+        tz_list.push(helpers::OutputTime {
+            timezone_name: tz_name.clone(),
+            timezone_nickname: saved.nickname.clone(),
+            displayed_name: match &saved.nickname {
+                Some(t) => format!("[{}] {}", t, tz_name),
+                None => tz_name,
+            },
+            day_offset: day_diff,
+            day_offset_str: offset_string,
+            timestamp: converted_time.naive_local().and_utc().timestamp()
+                - if whole_minutes {
+                    converted_time.second() as i64
+                } else {
+                    0
+                },
+            epoch: converted_time.timestamp(),
+            timestring: helpers::format_time(
+                if whole_minutes {
+                    converted_time.time().with_second(0).unwrap()
                 } else {
-                    offset_string = "".to_string();
+                    converted_time.time()
+                },
+                options.subsec,
+            ),
+            separator: saved.separator,
+            separator_style: saved.separator_style.clone(),
+            pinned: saved.pinned,
+            note: saved.note.clone(),
+            is_dst: !converted_time.offset().dst_offset().is_zero(),
+            utc_offset_seconds: converted_time.offset().fix().local_minus_utc(),
+            iso_week: converted_time.iso_week().week(),
+            ordinal: converted_time.ordinal(),
+            // From the UTC offsets, so it keeps its sign when the zone is on another date.
+            delta: converted_time.offset().fix().local_minus_utc()
+                - anchor_time.offset().local_minus_utc(),
+        });
+    }
+    if options.cross_day_only {
+        tz_list.retain(|t| t.day_offset != 0);
+    }
+    if options.same_day_only {
+        tz_list.retain(|t| t.day_offset == 0);
+    }
+
+    match options.sort.as_deref() {
+        // The reference first, then each zone in order of how far ahead it is, wrapping round so
+        // zones behind come last.
+        Some("from-pivot") => tz_list.sort_by_key(|k| (k.delta.rem_euclid(86400), k.timestamp)),
+        _ => tz_list.sort_by_key(|k| k.timestamp),
+    }
+    if options.reverse {
+        tz_list.reverse();
+    }
+
+    // Pinned zones go first, in the order they were saved, whichever way the rest are sorted.
+    let mut pinned_list: Vec<helpers::OutputTime> = ctx
+        .listed
+        .iter()
+        .filter(|t| t.pinned)
+        .filter_map(|t| {
+            tz_list
+                .iter()
+                .find(|item| item.timezone_name == t.timezone_name)
+                .cloned()
+        })
+        .collect();
+    tz_list.retain(|item| !item.pinned);
+    pinned_list.append(&mut tz_list);
+
+    TimeList {
+        header: fmt_string,
+        header_zone,
+        header_time: helpers::format_time(header_datetime.time(), options.subsec),
+        header_date: header_datetime.date_naive(),
+        epoch: header_datetime.timestamp(),
+        url_base: config.url_base.clone(),
+        times: pinned_list,
+    }
+}
+
+fn format_times(list: TimeList, options: &RenderOptions) -> String {
+    let mut output: String = "".to_string();
+
+    let output_fmt: String = match &options.output_format {
+        Some(t) => t.to_string(),
+        None => "pretty".to_string(),
+    };
+    // The pretty table, drawn as text so it survives being shared as an image.
+    if output_fmt == "svg" {
+        let pretty_options = RenderOptions {
+            output_format: Some("pretty".to_string()),
+            ..options.clone()
+        };
+        return helpers::svg_document(&format_times(list, &pretty_options));
+    }
+    let tz_list = list.times;
+    // With either clamp the name column fits the longest name instead of `--pretty-width`.
+    let sized_options;
+    let options = if options.min_width.is_some() || options.max_width.is_some() {
+        let longest = tz_list
+            .iter()
+            .map(|t| helpers::row_name(t, options).chars().count())
+            .chain([list.header.chars().count()])
+            .max()
+            .unwrap_or(0);
+        let width = longest
+            .max(options.min_width.unwrap_or(0))
+            .min(options.max_width.unwrap_or(usize::MAX));
+        sized_options = RenderOptions {
+            pretty_width: width,
+            ..options.clone()
+        };
+        &sized_options
+    } else {
+        options
+    };
+    let width = options.pretty_width;
+
+    if output_fmt == "pretty" && tz_list.is_empty() && options.cross_day_only {
+        output += "Every timezone is on the same day.\n";
+    } else if output_fmt == "pretty" && tz_list.is_empty() && options.same_day_only {
+        output += "Every timezone is on a different day.\n";
+    } else if output_fmt == "pretty" && tz_list.is_empty() {
+        output += "No timezones defined. Add one with `tc d add <zone>`.\n";
+    } else if output_fmt == "pretty" {
+        match &options.header_format {
+            Some(t) => {
+                output += &format!(
+                    "{}\n\n",
+                    t.replace("{zone}", &list.header_zone)
+                        .replace("{time}", &list.header_time)
+                        .replace("{date}", &list.header_date.to_string())
+                )
+            }
+            None => {
+                // Lines up with the rows, which get a gutter for the `--highlight` marker. Only the
+                // rows are cut to fit, the header is always shown whole.
+                let gutter = if options.highlight.is_some() {
+                    "  "
+                } else {
+                    ""
+                };
+                output += &format!(
+                    "{0}{1: <2$} {3}\n\n",
+                    gutter, list.header, width, list.header_time
+                )
+            }
+        }
+        match options.group_by.as_deref() {
+            Some("day") => {
+                let mut days: Vec<i32> = tz_list.iter().map(|t| t.day_offset).collect();
+                days.sort();
+                days.dedup();
+                if options.reverse {
+                    days.reverse();
                 }
-            } else {
-                let offset_time = offset_comparison_datetime.local_time.unwrap();
-
-                if converted_time.day() != offset_time.day() {
-                    let converted_ts = helpers::convert_date_to_timestamp(
-                        converted_time.year(),
-                        converted_time.ordinal0(),
-                    );
-                    let local_ts = helpers::convert_date_to_timestamp(
-                        offset_time.year(),
-                        offset_time.ordinal0(),
-                    );
-                    if converted_ts > local_ts {
-                        day_diff = converted_ts - local_ts;
-                        offset_string = format!("(+{}", day_diff);
-                    } else {
-                        day_diff = local_ts - converted_ts;
-                        offset_string = format!("(-{}", day_diff);
+                for (i, day) in days.iter().enumerate() {
+                    if i > 0 {
+                        output += "\n";
                     }
-                    if day_diff == 1 {
-                        offset_string += " day)";
-                    } else {
-                        offset_string += " days)";
+                    output += &format!("{}\n", helpers::day_label(*day));
+                    // The group header already says which day it is.
+                    let group: Vec<&helpers::OutputTime> =
+                        tz_list.iter().filter(|t| t.day_offset == *day).collect();
+                    for (j, item) in group.iter().enumerate() {
+                        output += &helpers::pretty_row(item, options, false, j + 1 == group.len());
                     }
-                } else {
-                    offset_string = "".to_string();
                 }
             }
-            tz_list.push(helpers::OutputTime {
-                timezone_name: tz_name.clone(),
-                timezone_nickname: match &config.timezones[contains.0 as usize].nickname {
-                    Some(t) => Some(t.to_string()),
-                    None => None,
-                },
-                displayed_name: match &config.timezones[contains.0 as usize].nickname {
-                    Some(t) => format!("[{}] {}", t.to_string(), tz_name),
-                    None => tz_name,
-                },
-                day_offset: day_diff,
-                day_offset_str: offset_string,
-                timestamp: converted_time.naive_local().and_utc().timestamp(),
-                timestring: converted_time.time().to_string(),
-                separator: config.timezones[contains.0 as usize].separator,
-            });
+            _ => {
+                for (j, item) in tz_list.iter().enumerate() {
+                    output += &helpers::pretty_row(item, options, true, j + 1 == tz_list.len());
+                }
+            }
         }
-    }
-
-    tz_list.sort_by_key(|k| k.timestamp);
-
-    if output_fmt == "pretty" {
-        for item in tz_list {
-            output += &format!(
-                "{0: <25} {1} {2}\n",
-                item.displayed_name, item.timestring, item.day_offset_str
-            );
-            if item.separator {
-                output += &format!("----------------------------------\n");
+    } else if output_fmt == "csv" || output_fmt == "tsv" {
+        let (delimiter, field): (&str, fn(&str) -> String) = if output_fmt == "tsv" {
+            ("\t", helpers::tsv_field)
+        } else {
+            (",", helpers::csv_field)
+        };
+        match &options.columns {
+            Some(columns) => output += &helpers::columns_table(&tz_list, columns, delimiter, field),
+            None => {
+                output += &format!(
+                    "{}\n",
+                    [
+                        "Timezone Name",
+                        "Timezone Nickname",
+                        "Day Offset",
+                        "Timestring",
+                        "Timestamp",
+                        "Note",
+                        "UTC Offset Seconds",
+                        "ISO Week",
+                        "Ordinal"
+                    ]
+                    .join(delimiter)
+                );
+                for item in tz_list {
+                    let nickname = match item.timezone_nickname {
+                        Some(t) => t,
+                        None => "null".to_string(),
+                    };
+                    let fields = [
+                        field(&item.timezone_name),
+                        field(&nickname),
+                        item.day_offset.to_string(),
+                        field(&item.timestring),
+                        item.timestamp.to_string(),
+                        field(item.note.as_deref().unwrap_or("")),
+                        item.utc_offset_seconds.to_string(),
+                        item.iso_week.to_string(),
+                        item.ordinal.to_string(),
+                    ];
+                    output += &format!("{}\n", fields.join(delimiter));
+                }
             }
         }
-    } else if output_fmt == "csv" {
-        output += "Timezone Name,Timezone Nickname,Day Offset,Timestring,Timestamp\n";
+    } else if output_fmt == "plain" {
+        for item in tz_list {
+            output += &format!("{}\t{}\n", item.timezone_name, item.epoch);
+        }
+    } else if output_fmt == "html" {
+        output += "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>tc</title></head>\n<body>\n<table>\n";
+        output += "<tr><th>Timezone Name</th><th>Timezone Nickname</th><th>Time</th><th>Day Offset</th></tr>\n";
         for item in tz_list {
             let nickname = match item.timezone_nickname {
                 Some(t) => t,
-                None => "null".to_string(),
+                None => "".to_string(),
             };
             output += &format!(
-                "{0},{1},{2},{3},{4}\n",
-                item.timezone_name, nickname, item.day_offset, item.timestring, item.timestamp
+                "<tr><td>{0}</td><td>{1}</td><td>{2}</td><td>{3}</td></tr>\n",
+                helpers::html_escape(&item.timezone_name),
+                helpers::html_escape(&nickname),
+                helpers::html_escape(&item.timestring),
+                helpers::html_escape(&item.day_offset_str)
+            );
+        }
+        output += "</table>\n</body>\n</html>\n";
+    } else if output_fmt == "oneline" {
+        let entries: Vec<String> = tz_list
+            .iter()
+            .map(|item| {
+                let name = match &item.timezone_nickname {
+                    Some(t) => t.to_string(),
+                    None => helpers::city_name(&item.timezone_name),
+                };
+                let mut entry = format!("{} {}", name, &item.timestring[..5]);
+                // Day offsets are left out to keep the line short, unless asked for with deltas.
+                if options.delta {
+                    entry += &format!(" ({})", helpers::format_delta(item.delta));
+                    if !item.day_offset_str.is_empty() {
+                        entry += &format!(" {}", item.day_offset_str);
+                    }
+                }
+                entry
+            })
+            .collect();
+        output += &entries.join(&options.oneline_separator);
+    } else if output_fmt == "url" {
+        let instant = DateTime::from_timestamp(list.epoch, 0).unwrap();
+        let zones: Vec<String> = tz_list
+            .iter()
+            .map(|t| helpers::url_encode(&t.timezone_name))
+            .collect();
+        // timeanddate only takes its own place ids, and 1440 is its id for UTC, so its link
+        // carries the instant alone. Other tools get the zones by name.
+        output += &match &list.url_base {
+            None => format!(
+                "{}?iso={}&p1=1440",
+                DEFAULT_URL_BASE,
+                instant.format("%Y%m%dT%H%M%S")
+            ),
+            Some(base) => format!(
+                "{}?iso={}&zones={}",
+                base,
+                instant.format("%Y%m%dT%H%M%SZ"),
+                zones.join(",")
+            ),
+        };
+    } else if output_fmt == "count" {
+        output += &tz_list.len().to_string();
+    } else if output_fmt == "template" {
+        let template = options.template.as_deref().unwrap_or_default();
+        let mut sections: Vec<String> = Vec::new();
+        // Headers and footers from `--format-file` fill in like `--header-format`.
+        let fill_header = |t: &str| {
+            t.replace("{zone}", &list.header_zone)
+                .replace("{time}", &list.header_time)
+                .replace("{date}", &list.header_date.to_string())
+        };
+        if let Some(t) = &options.template_header {
+            sections.push(fill_header(t));
+        }
+        if let serde_json::Value::Array(zones) = helpers::zones_json(&tz_list, None) {
+            sections.extend(
+                zones
+                    .iter()
+                    .map(|zone| helpers::fill_template(template, zone)),
             );
         }
+        if let Some(t) = &options.template_footer {
+            sections.push(fill_header(t));
+        }
+        output += &sections.join("\n");
     } else if output_fmt == "json" {
-        output += &format!("{}", serde_json::to_string(&tz_list).unwrap());
+        let zones = helpers::zones_json(&tz_list, options.columns.as_deref());
+        output += &serde_json::to_string(&zones).unwrap();
     } else if output_fmt == "json_pretty" {
-        output += &format!("{}", serde_json::to_string_pretty(&tz_list).unwrap());
+        let zones = helpers::zones_json(&tz_list, options.columns.as_deref());
+        output += &serde_json::to_string_pretty(&zones).unwrap();
     }
-    return Some(output);
+    output
 }
 
-pub fn get_unix_timestamp(time: Option<String>) -> i64 {
-    let datetime = helpers::get_comparison_date_time(time, None);
+// Renders a single time from `options.time`, or each of `times` when more than one is given.
+pub fn render(ctx: &RenderContext, options: &RenderOptions, times: &[String]) -> Option<String> {
+    if let Some(columns) = &options.columns {
+        if let Some(t) = columns
+            .iter()
+            .find(|t| !helpers::OUTPUT_COLUMNS.contains(&t.as_str()))
+        {
+            eprintln!(
+                "Unknown column {}! Valid columns are: {}",
+                t,
+                helpers::OUTPUT_COLUMNS.join(", ")
+            );
+            return None;
+        }
+    }
+    // Resolved to a full name once, so rows can be compared against it while drawing.
+    let resolved;
+    let options = match &options.highlight {
+        Some(input) => {
+            let tz = resolve_zone(input, &ctx.config).zone(input, "Timezone not found!")?;
+            if !ctx.listed.iter().any(|t| t.timezone_name == tz.name()) {
+                eprintln!("{} isn't one of the timezones being shown!", tz.name());
+                return None;
+            }
+            resolved = RenderOptions {
+                highlight: Some(tz.name().to_string()),
+                ..options.clone()
+            };
+            &resolved
+        }
+        None => options,
+    };
+    if let Some(pivots) = options
+        .timezone_input
+        .as_deref()
+        .and_then(|t| split_pivots(&ctx.config, t))
+    {
+        return render_pivots(ctx, options, times, &pivots);
+    }
+    if options.output_format.as_deref() == Some("template") {
+        let template = match &options.template {
+            Some(t) => t,
+            None => {
+                eprintln!("Template output needs a --template!");
+                return None;
+            }
+        };
+        if let Some(t) = helpers::template_placeholders(template)
+            .iter()
+            .find(|t| !helpers::OUTPUT_COLUMNS.contains(&t.as_str()))
+        {
+            eprintln!(
+                "Unknown placeholder {{{}}}! Valid placeholders are: {}",
+                t,
+                helpers::OUTPUT_COLUMNS.join(", ")
+            );
+            return None;
+        }
+    }
+    if times.len() > 1 {
+        return render_time_slots(ctx, options, times);
+    }
+    let options = RenderOptions {
+        time: times.first().cloned().or_else(|| options.time.clone()),
+        ..options.clone()
+    };
+    if options.time.is_none() {
+        let now = Utc::now();
+        return render_instants(ctx, &options, &[now]).map(|t| t.concat());
+    }
+    let list = collect_times(ctx, &options)?;
+    Some(format_times(list, &options))
+}
+
+// Renders the same times once for each of a comma-separated list of reference zones. Like
+// `render_time_slots`, json nests each render under the zone it was read in and everything else
+// gets a labelled block per zone.
+fn render_pivots(
+    ctx: &RenderContext,
+    options: &RenderOptions,
+    times: &[String],
+    pivots: &[String],
+) -> Option<String> {
+    let output_fmt: String = match &options.output_format {
+        Some(t) => t.to_string(),
+        None => "pretty".to_string(),
+    };
+    let json = output_fmt == "json" || output_fmt == "json_pretty";
+
+    let mut blocks: Vec<String> = [].to_vec();
+    let mut nested: Vec<helpers::OutputPivot> = Vec::new();
+    for pivot in pivots {
+        let pivot_options = RenderOptions {
+            timezone_input: Some(pivot.to_string()),
+            output_format: if json {
+                Some("json".to_string())
+            } else if output_fmt == "svg" {
+                Some("pretty".to_string())
+            } else {
+                options.output_format.clone()
+            },
+            ..options.clone()
+        };
+        let output = render(ctx, &pivot_options, times)?;
+        if json {
+            nested.push(helpers::OutputPivot {
+                timezone: pivot.to_string(),
+                times: serde_json::from_str(&output).unwrap(),
+            });
+        } else {
+            blocks.push(format!("[{}]\n{}", pivot, output));
+        }
+    }
+
+    if output_fmt == "json" {
+        Some(serde_json::to_string(&nested).unwrap())
+    } else if output_fmt == "json_pretty" {
+        Some(serde_json::to_string_pretty(&nested).unwrap())
+    } else if output_fmt == "svg" {
+        Some(helpers::svg_document(&blocks.join("\n")))
+    } else {
+        Some(blocks.join("\n"))
+    }
+}
 
-    return datetime.unwrap().local_time.unwrap().timestamp();
+// Splits a `--format-file` into its `[header]`, `[row]` and `[footer]` sections. A file without any
+// section lines is all row.
+pub fn parse_format_file(text: &str) -> Option<(Option<String>, String, Option<String>)> {
+    let mut sections: [Vec<&str>; 3] = [Vec::new(), Vec::new(), Vec::new()];
+    let mut current = 1;
+    let mut seen = [false; 3];
+    for line in text.lines() {
+        let section = match line.trim() {
+            "[header]" => 0,
+            "[row]" => 1,
+            "[footer]" => 2,
+            _ => {
+                sections[current].push(line);
+                continue;
+            }
+        };
+        current = section;
+        seen[section] = true;
+    }
+    if seen.iter().any(|t| *t) && !seen[1] {
+        eprintln!("Format file has no [row] section!");
+        return None;
+    }
+    let [header, row, footer] = sections.map(|t| t.join("\n"));
+    Some((seen[0].then_some(header), row, seen[2].then_some(footer)))
+}
+
+// Renders the saved zones at each instant, resolving the reference and anchor only once. Any time
+// in `options` is ignored.
+pub fn render_instants(
+    ctx: &RenderContext,
+    options: &RenderOptions,
+    instants: &[DateTime<Utc>],
+) -> Option<Vec<String>> {
+    let reference = resolve_reference(&ctx.config, options)?;
+    Some(
+        instants
+            .iter()
+            .map(|t| {
+                format_times(
+                    list_times(ctx, options, &reference, reference.at(*t)),
+                    options,
+                )
+            })
+            .collect(),
+    )
+}
+
+// Renders each time in turn. json output becomes a single array with an entry per time, every
+// other format gets each block prefixed with the time it is for.
+fn render_time_slots(
+    ctx: &RenderContext,
+    options: &RenderOptions,
+    times: &[String],
+) -> Option<String> {
+    let output_fmt: String = match &options.output_format {
+        Some(t) => t.to_string(),
+        None => "pretty".to_string(),
+    };
+
+    let mut blocks: Vec<String> = [].to_vec();
+    let mut slots: Vec<helpers::OutputSlot> = Vec::new();
+    for time in times {
+        let slot_options = RenderOptions {
+            time: Some(time.to_string()),
+            // svg blocks are drawn together into one image below.
+            output_format: if output_fmt == "svg" {
+                Some("pretty".to_string())
+            } else {
+                options.output_format.clone()
+            },
+            ..options.clone()
+        };
+        let list = collect_times(ctx, &slot_options)?;
+        if output_fmt == "json" || output_fmt == "json_pretty" {
+            slots.push(helpers::OutputSlot {
+                time: time.to_string(),
+                zones: helpers::zones_json(&list.times, options.columns.as_deref()),
+            });
+        } else {
+            blocks.push(format!("[{}]\n{}", time, format_times(list, &slot_options)));
+        }
+    }
+
+    if output_fmt == "json" {
+        Some(serde_json::to_string(&slots).unwrap())
+    } else if output_fmt == "json_pretty" {
+        Some(serde_json::to_string_pretty(&slots).unwrap())
+    } else if output_fmt == "svg" {
+        Some(helpers::svg_document(&blocks.join("\n")))
+    } else {
+        Some(blocks.join("\n"))
+    }
+}
+
+// One sentence per saved zone, like `In America/New_York it will be 09:00 (Mon)`, for pasting into
+// event announcements.
+pub fn render_schedule(ctx: &RenderContext, options: &RenderOptions, json: bool) -> Option<String> {
+    let list = collect_times(ctx, options)?;
+    let entries: Vec<helpers::ScheduleEntry> = list
+        .times
+        .iter()
+        .map(|item| {
+            // `timestamp` is the zone's wall-clock time read as UTC.
+            let local = DateTime::from_timestamp(item.timestamp, 0)
+                .unwrap()
+                .naive_utc();
+            let time = local.format("%H:%M").to_string();
+            let weekday = local.format("%a").to_string();
+            helpers::ScheduleEntry {
+                timezone_name: item.timezone_name.clone(),
+                timezone_nickname: item.timezone_nickname.clone(),
+                date: local.date().to_string(),
+                sentence: format!(
+                    "In {} it will be {} ({})",
+                    item.timezone_name, time, weekday
+                ),
+                weekday,
+                time,
+            }
+        })
+        .collect();
+
+    if json {
+        return Some(serde_json::to_string(&entries).unwrap());
+    }
+    let lines: Vec<String> = entries.into_iter().map(|t| t.sentence).collect();
+    Some(lines.join("\n"))
+}
+
+// A daily cron line and systemd `OnCalendar=` line for the time, moved to UTC.
+pub fn render_cron(ctx: &RenderContext, options: &RenderOptions) -> Option<String> {
+    let list = collect_times(ctx, options)?;
+    let utc = DateTime::from_timestamp(list.epoch, 0)?;
+    // January and July land either side of any DST change, in either hemisphere. Going through
+    // the reference covers the system local timezone as well as a named one.
+    let reference = resolve_reference(&ctx.config, options)?;
+    let offset_in = |month| {
+        let date = NaiveDate::from_ymd_opt(utc.year(), month, 1).unwrap();
+        reference
+            .at(date.and_hms_opt(0, 0, 0).unwrap().and_utc())
+            .offset()
+            .fix()
+    };
+    if offset_in(1) != offset_in(7) {
+        let zone = match reference.header_zone.as_str() {
+            "Local" => "The local timezone",
+            t => t,
+        };
+        eprintln!(
+            "{} changes offset during the year, so this UTC time will drift by the DST shift.",
+            zone
+        );
+    }
+    Some(format!(
+        "{} {} * * *\nOnCalendar=*-*-* {} UTC",
+        utc.minute(),
+        utc.hour(),
+        utc.format("%H:%M:%S")
+    ))
+}
+
+// Roughly what Discord shows for `<t:timestamp:style>`, in this machine's local time.
+pub fn discord_preview(timestamp: i64, style: &str) -> Option<String> {
+    let time = DateTime::from_timestamp(timestamp, 0)?.with_timezone(&chrono::Local);
+    let format = match style {
+        "t" => "%-I:%M %p",
+        "T" => "%-I:%M:%S %p",
+        "d" => "%m/%d/%Y",
+        "D" => "%B %-d, %Y",
+        "F" => "%A, %B %-d, %Y %-I:%M %p",
+        "R" => return Some(helpers::relative_time(timestamp - Utc::now().timestamp())),
+        _ => "%B %-d, %Y %-I:%M %p",
+    };
+    Some(time.format(format).to_string())
+}
+
+pub fn get_unix_timestamp(
+    time: Option<String>,
+    from_zone: Option<String>,
+    prefer: Option<Prefer>,
+    bare_epoch: bool,
+) -> Option<i64> {
+    let (timezone, fixed_offset) = resolve_from_zone(from_zone)?;
+    match timestamp_for(time, timezone, fixed_offset, prefer, bare_epoch) {
+        Ok(t) => Some(t),
+        Err(e) => {
+            eprintln!("{}", e);
+            None
+        }
+    }
+}
+
+// Each line of `path` alongside its timestamp, as `tc u` would give it. Lines that don't parse
+// are reported with their line number and skipped.
+pub fn batch_unix_timestamps(
+    path: String,
+    from_zone: Option<String>,
+    prefer: Option<Prefer>,
+    bare_epoch: bool,
+) -> Option<Vec<(String, i64)>> {
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(t) => t,
+        Err(_e) => {
+            eprintln!("Error reading {}!", path);
+            return None;
+        }
+    };
+    let (timezone, fixed_offset) = resolve_from_zone(from_zone)?;
+
+    let mut res: Vec<(String, i64)> = [].to_vec();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        match timestamp_for(
+            Some(line.to_string()),
+            timezone,
+            fixed_offset,
+            prefer,
+            bare_epoch,
+        ) {
+            Ok(t) => res.push((line.to_string(), t)),
+            Err(e) => eprintln!("Line {}: {}", i + 1, e),
+        }
+    }
+    Some(res)
+}
+
+fn resolve_from_zone(from_zone: Option<String>) -> Option<(Option<Tz>, Option<FixedOffset>)> {
+    match from_zone {
+        Some(input) => match helpers::parse_fixed_offset(&input) {
+            Some(t) => Some((None, Some(t))),
+            None => {
+                let config = match config::load_config() {
+                    Ok(t) => t,
+                    Err(_e) => {
+                        return None;
+                    }
+                };
+                resolve_zone(&input, &config)
+                    .zone(&input, "Timezone not found!")
+                    .map(|t| (Some(t), None))
+            }
+        },
+        None => Some((None, None)),
+    }
+}
+
+fn timestamp_for(
+    time: Option<String>,
+    timezone: Option<Tz>,
+    fixed_offset: Option<FixedOffset>,
+    prefer: Option<Prefer>,
+    bare_epoch: bool,
+) -> Result<i64, String> {
+    // `@1700000000` is already a timestamp, so it passes straight through (minus any fraction)
+    // to be re-emitted in the chosen format.
+    if let Some(epoch) = time
+        .as_deref()
+        .and_then(|t| helpers::input_epoch(t, bare_epoch))
+    {
+        return Ok(epoch);
+    }
+    // Now is already an instant. Rebuilding it from the wall-clock time would put it on the
+    // local date, a day out when the zone is on another one.
+    if matches!(
+        time.as_deref().map(helpers::parse_time_of_day),
+        None | Some(Ok(None))
+    ) {
+        return Ok(Utc::now().timestamp());
+    }
+    let t = helpers::get_comparison_date_time(time, timezone, fixed_offset, prefer)?;
+    Ok(t.as_fixed().timestamp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(timezone_name: &str, nickname: Option<&str>) -> config::SavedTimezones {
+        config::SavedTimezones {
+            timezone_name: timezone_name.to_string(),
+            nickname: nickname.map(|t| t.to_string()),
+            separator: false,
+            pinned: false,
+            note: None,
+            separator_style: None,
+            last_used: None,
+            extra: HashMap::new(),
+        }
+    }
+
+    fn context(timezones: Vec<config::SavedTimezones>) -> RenderContext {
+        RenderContext::new(config::SavedDefines {
+            timezones,
+            ..Default::default()
+        })
+    }
+
+    // The rendered text for `time` read in `timezone`, which is always given so `TZ` can't leak in.
+    fn render_at(
+        ctx: &RenderContext,
+        timezone: &str,
+        time: &str,
+        options: RenderOptions,
+    ) -> String {
+        let options = RenderOptions {
+            timezone_input: Some(timezone.to_string()),
+            time: Some(time.to_string()),
+            ..options
+        };
+        render(ctx, &options, &[]).unwrap()
+    }
+
+    // Each zone's row for `time` read in `timezone`, keyed by name.
+    fn times_at(
+        ctx: &RenderContext,
+        timezone: &str,
+        time: &str,
+        options: RenderOptions,
+    ) -> HashMap<String, helpers::OutputTime> {
+        let options = RenderOptions {
+            timezone_input: Some(timezone.to_string()),
+            time: Some(time.to_string()),
+            ..options
+        };
+        let list = collect_times(ctx, &options).unwrap();
+        list.times
+            .into_iter()
+            .map(|t| (t.timezone_name.clone(), t))
+            .collect()
+    }
+
+    #[test]
+    fn csv_nickname_with_comma() {
+        let ctx = context(vec![saved("America/New_York", Some("East, Coast"))]);
+        let options = RenderOptions {
+            output_format: Some("csv".to_string()),
+            ..Default::default()
+        };
+        let output = render_at(&ctx, "UTC", "@0", options);
+        let row = output.lines().nth(1).unwrap();
+        assert!(row.starts_with("America/New_York,\"East, Coast\",-1,19:00:00,"));
+    }
+
+    #[test]
+    fn day_offset_before_1970() {
+        let ctx = context(vec![
+            saved("Asia/Tokyo", None),
+            saved("America/New_York", None),
+        ]);
+        // 1969-12-31 09:00 in Tokyo, still the 30th in New York.
+        let times = times_at(&ctx, "Asia/Tokyo", "@-86400", Default::default());
+        assert_eq!(times["Asia/Tokyo"].day_offset, 0);
+        assert_eq!(times["America/New_York"].day_offset, -1);
+        assert_eq!(times["America/New_York"].timestring, "19:00:00");
+        // 1950-01-01 12:00 in New York, already the 2nd in Tokyo.
+        let times = times_at(&ctx, "America/New_York", "@-631090800", Default::default());
+        assert_eq!(times["America/New_York"].day_offset, 0);
+        assert_eq!(times["Asia/Tokyo"].day_offset, 1);
+    }
+
+    #[test]
+    fn delta_behind_across_date_line() {
+        let ctx = context(vec![
+            saved("Pacific/Kiritimati", None),
+            saved("Pacific/Noumea", None),
+            saved("America/Chicago", None),
+        ]);
+        // 2024-01-01 14:00 in Kiritimati. Chicago's clock reads 18:00, but on the day before.
+        let times = times_at(
+            &ctx,
+            "Pacific/Kiritimati",
+            "@1704067200",
+            Default::default(),
+        );
+        let noumea = &times["Pacific/Noumea"];
+        assert_eq!((noumea.delta, noumea.day_offset), (-3 * 3600, 0));
+        let chicago = &times["America/Chicago"];
+        assert_eq!(chicago.timestring, "18:00:00");
+        assert_eq!((chicago.delta, chicago.day_offset), (-20 * 3600, -1));
+        // And the other way round, from Chicago.
+        let times = times_at(&ctx, "America/Chicago", "@1704067200", Default::default());
+        let kiritimati = &times["Pacific/Kiritimati"];
+        assert_eq!((kiritimati.delta, kiritimati.day_offset), (20 * 3600, 1));
+        let options = RenderOptions {
+            delta: true,
+            ..Default::default()
+        };
+        let output = render_at(&ctx, "Pacific/Kiritimati", "@1704067200", options);
+        assert!(output.contains("-3h"));
+        assert!(output.contains("-20h"));
+    }
+
+    #[test]
+    fn multi_word_nickname() {
+        let ctx = context(vec![
+            saved("America/Los_Angeles", Some("west coast")),
+            saved("America/New_York", Some("coast")),
+        ]);
+        let name = |input: &str| match resolve_zone(input, &ctx.config) {
+            ResolveResult::Exact(tz) => Some(tz.name()),
+            _ => None,
+        };
+        assert_eq!(name("west coast"), Some("America/Los_Angeles"));
+        assert_eq!(name("West  Coast"), Some("America/Los_Angeles"));
+        assert_eq!(name("coast"), Some("America/New_York"));
+        // `tc t -t "west coast" 12:00`
+        let times = times_at(&ctx, "west coast", "12:00", Default::default());
+        assert_eq!(times["America/Los_Angeles"].timestring, "12:00:00");
+        assert_eq!(times["America/Los_Angeles"].delta, 0);
+    }
+
+    #[test]
+    fn am_pm_is_not_a_zone() {
+        let config = config::SavedDefines {
+            timezones: vec![saved("Asia/Tokyo", None)],
+            ..Default::default()
+        };
+        assert_eq!(split_time_and_zone(&config, "11 am"), None);
+        assert_eq!(split_time_and_zone(&config, "3 PM"), None);
+        assert_eq!(
+            split_time_and_zone(&config, "11 am Tokyo"),
+            Some(("11 am".to_string(), "Tokyo".to_string()))
+        );
+        let ctx = RenderContext::new(config);
+        let times = times_at(&ctx, "Asia/Tokyo", "12am", Default::default());
+        assert_eq!(times["Asia/Tokyo"].timestring, "00:00:00");
+    }
+
+    #[test]
+    fn whole_minute_input_stays_whole() {
+        // Liberia kept its -0:44:30 mean time offset until 1972.
+        let ctx = context(vec![saved("Africa/Monrovia", None)]);
+        let times = times_at(&ctx, "UTC", "@0", Default::default());
+        assert_eq!(times["Africa/Monrovia"].timestring, "23:15:00");
+        let times = times_at(&ctx, "UTC", "@1", Default::default());
+        assert_eq!(times["Africa/Monrovia"].timestring, "23:15:31");
+    }
+
+    #[test]
+    fn same_time_different_day_groups() {
+        // A whole day apart, so both clocks read the same.
+        let ctx = context(vec![
+            saved("Pacific/Kiritimati", None),
+            saved("Pacific/Honolulu", None),
+        ]);
+        let times = times_at(
+            &ctx,
+            "Pacific/Kiritimati",
+            "@1704067200",
+            Default::default(),
+        );
+        let (kiritimati, honolulu) = (&times["Pacific/Kiritimati"], &times["Pacific/Honolulu"]);
+        assert_eq!(kiritimati.timestring, honolulu.timestring);
+        assert_eq!((kiritimati.day_offset, honolulu.day_offset), (0, -1));
+        let options = RenderOptions {
+            group_by: Some("day".to_string()),
+            ..Default::default()
+        };
+        let output = render_at(&ctx, "Pacific/Kiritimati", "@1704067200", options);
+        let groups: Vec<&str> = output.split("\n\n").skip(1).collect();
+        assert_eq!(groups.len(), 2);
+        assert!(groups[0].starts_with("Yesterday\n"));
+        assert!(groups[0].contains("Pacific/Honolulu"));
+        assert!(!groups[0].contains("Pacific/Kiritimati"));
+        assert!(groups[1].starts_with("Today\n"));
+        assert!(groups[1].contains("Pacific/Kiritimati"));
+    }
+
+    #[test]
+    fn winter_abbreviation_is_ambiguous() {
+        // PST is both US Pacific winter time and Philippine time, whatever the time of year.
+        let offsets: Vec<i32> = abbreviation_offsets("PST")
+            .iter()
+            .map(|(t, _)| t.local_minus_utc())
+            .collect();
+        assert!(offsets.contains(&(-8 * 3600)));
+        assert!(offsets.contains(&(8 * 3600)));
+        let ctx = context(vec![saved("Asia/Tokyo", None)]);
+        let options = RenderOptions {
+            time: Some("15:00 PST".to_string()),
+            ..Default::default()
+        };
+        assert!(render(&ctx, &options, &[]).is_none());
+    }
+
+    #[test]
+    fn no_time_is_now_in_any_zone() {
+        for zone in ["Pacific/Kiritimati", "Pacific/Pago_Pago"] {
+            let tz = zone.parse::<Tz>().ok();
+            let timestamp = timestamp_for(None, tz, None, None, false).unwrap();
+            assert!((timestamp - Utc::now().timestamp()).abs() < 5);
+        }
+    }
+
+    #[test]
+    fn schedule_without_time_is_today_there() {
+        let ctx = context(vec![saved("Pacific/Kiritimati", None)]);
+        let options = RenderOptions {
+            timezone_input: Some("Pacific/Pago_Pago".to_string()),
+            ..Default::default()
+        };
+        let output = render_schedule(&ctx, &options, false).unwrap();
+        let weekday = Utc::now()
+            .with_timezone(&chrono_tz::Pacific::Kiritimati)
+            .format("%a")
+            .to_string();
+        assert!(output.ends_with(&format!("({})", weekday)));
+    }
+
+    #[test]
+    fn header_is_not_truncated() {
+        let ctx = context(vec![saved("Asia/Tokyo", None)]);
+        let output = render_at(&ctx, "America/Los_Angeles", "12:00", Default::default());
+        assert!(output.starts_with("Time for America/Los_Angeles 12:00:00\n"));
+    }
+
+    #[test]
+    fn historical_dst_rules() {
+        // Moscow kept summer time until 2011 and has been on UTC+3 all year since 2014.
+        let ctx = context(vec![saved("Europe/Moscow", None)]);
+        let times = times_at(&ctx, "UTC", "@1277985600", Default::default());
+        let moscow = &times["Europe/Moscow"];
+        assert_eq!((moscow.utc_offset_seconds, moscow.is_dst), (14400, true));
+        assert_eq!(moscow.timestring, "16:00:00");
+        let times = times_at(&ctx, "UTC", "@1782907200", Default::default());
+        let moscow = &times["Europe/Moscow"];
+        assert_eq!((moscow.utc_offset_seconds, moscow.is_dst), (10800, false));
+        assert_eq!(moscow.timestring, "15:00:00");
+    }
 }